@@ -1,52 +1,73 @@
 /// Here' we'll only import the CLI structure
 /// inside of main so it can be used when it's called
-use clap::{ArgMatches, Command};
+use clap::{Arg, ArgMatches, Command};
+use colored::*;
 // Local imports
 mod options;
 mod utilities;
 // Use the methods from the modules
-use options::{bump_version, create_changesets, list_changesets};
-use utilities::create_subcommands;
+use options::{
+    bump_version, changelog, check_changesets, create_changesets, doctor, list_changesets,
+    pre_release, release_notes,
+};
+use utilities::{create_subcommands, Config, VersionWiseError};
 
 fn main() {
-    // Create the methods
-    let (create, list, bump) = create_subcommands();
     // Instance the App
     let mut app = Command::new("VersionWise :: Project management with Changesets")
-        .subcommand(create)
-        .subcommand(list)
-        .subcommand(bump)
+        .subcommands(create_subcommands())
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("PATH")
+                .help("Configuration file to use, instead of searching for `versionwise.toml`, `.versionwise.toml`, `pyproject.toml`, `Cargo.toml` and `package.json`")
+                .global(true),
+        )
         .long_about("This module allows you to easily create and manage changesets for your project, providing a structured approach to documenting and tracking changes throughout the development process. Changesets help teams maintain better control over project updates, ensuring clear communication and effective collaboration. With this tool, you can streamline the process of recording changes, facilitating smoother project management and development workflows.")
         .about("Module for creating and using changesets to manage changes in team projects.
 
 [Commands]
 \t- `create`: Create a new changeset
 \t- `list`: List the current changes and how they affect the current version
-\t- `bump`: Release the new version and new changelog. Delete all the current changesets."
+\t- `bump`: Release the new version and new changelog. Delete or archive the released changesets.
+\t- `check`: Fail if the current branch changes source files without adding a changeset.
+\t- `pre`: Enter or exit the pre-release mode.
+\t- `doctor`: Check that every version file has the same version.
+\t- `notes`: Print the release notes of a version from the CHANGELOG.
+\t- `changelog rebuild`: Write the CHANGELOG again from the archived changesets."
         );
     // Add the methods to the app method
     // Search for the matches
     let matches: ArgMatches = app.clone().get_matches();
-    // Search for the matches
-    match matches.subcommand() {
-        // Create
-        Some(("create", _)) => {
-            // Instance the app method
-            create_changesets()
-        }
-        // List
-        Some(("list", _)) => {
-            // Instance the app method
-            list_changesets()
-        }
-        // Bump
-        Some(("bump", _)) => {
-            // Instance the app method
-            bump_version()
-        }
-        _ => {
-            // Manage the default cases for this project
-            println!("{}", app.render_usage());
-        }
+    // Without a subcommand, just show how to use it
+    let Some((command, sub_matches)) = matches.subcommand() else {
+        println!("{}", app.render_usage());
+        return;
+    };
+    // Load the configuration once, and use it in the command
+    let result: Result<(), VersionWiseError> = Config::load(matches.get_one::<String>("config"))
+        .and_then(|config| match command {
+            // Create
+            "create" => create_changesets(sub_matches, &config),
+            // List
+            "list" => list_changesets(sub_matches, &config),
+            // Bump
+            "bump" => bump_version(sub_matches, &config),
+            // Check
+            "check" => check_changesets(sub_matches, &config),
+            // Pre-release
+            "pre" => pre_release(sub_matches, &config),
+            // Doctor
+            "doctor" => doctor(sub_matches, &config),
+            // Notes
+            "notes" => release_notes(sub_matches, &config),
+            // Changelog
+            "changelog" => changelog(sub_matches, &config),
+            _ => unreachable!("Unknown subcommand `{}`", command),
+        });
+    // Show the errors in a friendly way, and exit with their code
+    if let Err(error) = result {
+        eprintln!("{} {}", "Error:".red().bold(), error);
+        std::process::exit(error.exit_code());
     }
 }
@@ -1,28 +1,161 @@
 /// ================================ ///
 ///         OPTIONS :: Bump          ///
 /// ================================ ///
+use clap::ArgMatches;
+use colored::*;
+use similar::TextDiff;
+use std::fs;
+use std::path::Path;
 // Local imports
+use crate::utilities::changelog::Release;
+use crate::utilities::changelog_outputs::{output_entry, render_changelog_outputs};
+use crate::utilities::changelog_utils::{release_entry, EntryDetails};
+use crate::utilities::config::NoBumpRelease;
 use crate::utilities::{
-    create_changelog, find_largest_version, get_current_changesets, new_changelog_entry,
-    open_changelog,
+    check_versions_consistency, consume_changesets, get_current_changesets, next_release,
+    open_changelog, render_changelog, unreleased_entry, version_path_updates, write_pre_state,
+    Config, NextRelease, VersionWiseError,
 };
 
-pub fn bump_version() {
+/// Print what the bump would do, without touching any file
+fn print_dry_run(
+    config: &Config,
+    release: &NextRelease,
+    new_entry: &[String],
+    details: &EntryDetails,
+) -> Result<(), VersionWiseError> {
+    let new_version = &release.version;
+    // Print the version that would be released
+    match release.bumps_version {
+        true => println!("# New version to be bumped: v{}.\n", new_version.blue()),
+        false => println!(
+            "# No version bump. The changesets are recorded under v{}.\n",
+            new_version.blue()
+        ),
+    }
+    // Print the entry to be included in the CHANGELOG, and in the other outputs
+    println!("{}\n", format!("## {} entry", config.changelog).bold());
+    println!("{}", new_entry.join(""));
+    for output in &config.changelog_outputs {
+        println!("{}\n", format!("## {} entry", output.path).bold());
+        println!(
+            "{}\n",
+            output_entry(config, output, &release.changesets, new_version, details)?.trim_end()
+        );
+    }
+    // Print the diff of every version file that would change
+    println!("{}\n", "## Version files".bold());
+    for (path, content, updated_content) in version_path_updates(config, new_version)? {
+        let diff = TextDiff::from_lines(&content, &updated_content);
+        print!(
+            "{}",
+            diff.unified_diff()
+                .context_radius(3)
+                .header(&format!("a/{}", path), &format!("b/{}", path))
+        );
+    }
+    // And the changesets that would be consumed
+    println!(
+        "\nDry run: {} changeset(s) would be consumed. Nothing has been written.",
+        release.changesets.len()
+    );
+    Ok(())
+}
+
+pub fn bump_version(matches: &ArgMatches, config: &Config) -> Result<(), VersionWiseError> {
+    // Don't release anything if the version files have drifted apart
+    check_versions_consistency(config)?;
     // First, get the changesets
-    let changesets = get_current_changesets();
-    // Find the current project version
-    let new_version = find_largest_version(&changesets).unwrap();
+    let changesets = get_current_changesets(config)?;
+    // Find the release to do with them
+    let release = next_release(config, changesets)?.ok_or_else(|| {
+        VersionWiseError::Version(
+            "There are no changesets to calculate the new version from.".to_string(),
+        )
+    })?;
+    // Changesets that don't bump the version wait for the next release, unless
+    // they're configured to be recorded under the current version
+    if !release.bumps_version && config.no_bump.release == NoBumpRelease::Skip {
+        println!("The pending changesets don't bump the version, so there's nothing to release. They'll be included in the next release.");
+        return Ok(());
+    }
+    let new_version = &release.version;
     // From here, parse the changesets as the new Changelog entry
-    let new_entry = new_changelog_entry(&changesets, &new_version);
+    // The details are taken before the version files change
+    let details = EntryDetails::upcoming(config)?;
+    let new_entry = release_entry(config, &release.changesets, new_version, &details)?;
+    // If this is a dry run, just show what would happen
+    if matches.get_flag("dry-run") {
+        return print_dry_run(config, &release, &new_entry, &details);
+    }
+    // Render every file before writing any of them, so an error doesn't
+    // leave a half-done release behind
+    let mut files: Vec<(String, String)> = version_path_updates(config, new_version)?
+        .into_iter()
+        .map(|(path, _, updated_content)| (path, updated_content))
+        .collect();
+    // Without a version bump, there might be nothing to write in the CHANGELOG
+    let writes_changelog =
+        release.bumps_version || release.changesets.iter().any(|c| config.in_changelog(c));
+    if writes_changelog {
+        files.push((
+            config.changelog.clone(),
+            changelog_content(config, &release, &new_entry)?,
+        ));
+        files.extend(render_changelog_outputs(
+            config,
+            &release.changesets,
+            new_version,
+            &details,
+            !release.bumps_version,
+        )?);
+    }
+    write_files(&files)?;
+    if writes_changelog {
+        println!("The `{}` and version has been updated!", config.changelog);
+    }
+    // By last, on a pre-release keep the changesets for the final release.
+    // Otherwise, they're not needed anymore
+    match release.pre_state {
+        Some(state) => write_pre_state(config, &state),
+        None => consume_changesets(config, new_version),
+    }
+}
+
+/// Write every rendered file. The folders that don't exist yet are created
+fn write_files(files: &[(String, String)]) -> Result<(), VersionWiseError> {
+    for (path, content) in files {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent).map_err(|e| VersionWiseError::io(path, e))?;
+        }
+        fs::write(path, content).map_err(|e| VersionWiseError::io(path, e))?;
+    }
+    Ok(())
+}
+
+/// Content of the CHANGELOG with the new entry at the top
+fn changelog_content(
+    config: &Config,
+    release: &NextRelease,
+    new_entry: &[String],
+) -> Result<String, VersionWiseError> {
     // Now, read the current CHANGESET file
-    let mut content = open_changelog();
-    // Find the index where it is the start of versions
-    let start_of_versions_index = content
-        .iter()
-        .position(|line| line.starts_with("## ["))
-        .unwrap_or(content.len());
-    //content_to_write.truncate(content_to_write.len() - 2);
-    content.insert(start_of_versions_index, new_entry.join("").to_string());
-    // Then, by last, write the content
-    create_changelog(content, &new_version)
+    let mut changelog = open_changelog(config)?;
+    let entry = Release::parse_entry(&new_entry.join(""))?;
+    match changelog.release_index(&release.version) {
+        // Changesets recorded under the current version are added to its release
+        Some(index) if !release.bumps_version => changelog.releases[index].merge(entry),
+        // Otherwise, write it before the previous releases, replacing the Unreleased section
+        _ => {
+            changelog.insert_release(entry, config.unreleased);
+        }
+    }
+    // Which starts again empty, if it's used
+    if config.unreleased {
+        changelog.set_unreleased(Release::parse_entry(
+            &unreleased_entry(config, &[])?.join(""),
+        )?);
+    }
+    // Then, render the content
+    render_changelog(config, &mut changelog)
 }
@@ -0,0 +1,35 @@
+/// ================================ ///
+///       OPTIONS :: Changelog       ///
+/// ================================ ///
+/// Maintain the CHANGELOG as a whole. `rebuild` writes it again from the
+/// archived changesets of every release.
+use clap::ArgMatches;
+// Local imports
+use crate::utilities::{rebuild_changelog, write_changelog, Config, VersionWiseError};
+
+fn rebuild(config: &Config, dry_run: bool) -> Result<(), VersionWiseError> {
+    let mut changelog = rebuild_changelog(config)?;
+    // On a dry run, just show the new CHANGELOG
+    if dry_run {
+        print!("{}", changelog.render());
+        return Ok(());
+    }
+    write_changelog(config, &mut changelog)?;
+    println!(
+        "The `{}` has been rebuilt with {} release(s)!",
+        config.changelog,
+        changelog
+            .releases
+            .iter()
+            .filter(|release| !release.is_unreleased())
+            .count()
+    );
+    Ok(())
+}
+
+pub fn changelog(matches: &ArgMatches, config: &Config) -> Result<(), VersionWiseError> {
+    match matches.subcommand() {
+        Some(("rebuild", sub_matches)) => rebuild(config, sub_matches.get_flag("dry-run")),
+        _ => unreachable!("The `changelog` command requires a subcommand"),
+    }
+}
@@ -1,31 +1,7 @@
 /// Just write the Changeset structure
 use std::cmp::Ordering;
-
-fn update_version(change: &str, version: String) -> String {
-    let mut new_version = String::new();
-    // We're going to update the version based on the change type
-    match change {
-        "MAJOR" => new_version = format!("{}.0.0", version.split('.').next().unwrap()),
-        "MINOR" => {
-            new_version = format!(
-                "{}.{}.0",
-                version.split('.').next().unwrap(),
-                version.split('.').nth(1).unwrap()
-            )
-        }
-        "PATCH" => {
-            new_version = format!(
-                "{}.{}.{}",
-                version.split('.').next().unwrap(),
-                version.split('.').nth(1).unwrap(),
-                version.split('.').nth(2).unwrap()
-            )
-        }
-        _ => {}
-    }
-    // Return the new version
-    new_version
-}
+// Local imports
+use crate::utilities::version_operations::ChangeType;
 
 /// Changeset structure, including all the necessary fields
 /// to process and create the new CHANGELOG.md
@@ -48,19 +24,22 @@ impl Changeset {
         message: String,
         version: String,
     ) -> Changeset {
-        // Get the new and updated version!
-        let new_version = update_version(&change, version);
-        // Initialize the changeset with the new version and the normal
-        // methods
+        // The version is already bumped by the version scheme, so
+        // initialize the changeset with the normal methods
         Changeset {
             name,
             change,
             modules,
             tag,
             message,
-            version: new_version,
+            version,
         }
     }
+
+    /// Whether the changeset is an internal change that doesn't bump the version
+    pub fn is_no_bump(&self) -> bool {
+        matches!(self.change.parse(), Ok(ChangeType::None))
+    }
 }
 
 // Implement the PartialEq to compare changesets between them
@@ -78,7 +57,7 @@ impl PartialEq for Changeset {
 // Implement a PartialOrd method to sort the classes
 impl PartialOrd for Changeset {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.name.cmp(&other.name))
+        Some(self.cmp(other))
     }
 }
 
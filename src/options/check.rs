@@ -0,0 +1,175 @@
+/// ================================ ///
+///         OPTIONS :: Check         ///
+/// ================================ ///
+/// Gate for the pull requests. It fails when the current branch changes
+/// source files, but it doesn't add any changeset.
+use clap::ArgMatches;
+use colored::*;
+use glob::{MatchOptions, Pattern};
+use std::path::Path;
+// Local imports
+use crate::utilities::pre_release::PRE_STATE_FILE;
+use crate::utilities::{
+    get_current_changesets, get_git_added_files, get_git_changed_files, Config, VersionWiseError,
+};
+
+/// Base reference used when neither the CLI nor the configuration specify one
+const DEFAULT_BASE: &str = "origin/main";
+
+/// Options used to compare the glob patterns against the paths
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Check if a changed path is covered by the module of a changeset.
+/// A module covers a path if it is the same file, one of its parent
+/// directories or a glob pattern that matches it.
+fn is_covered(path: &str, module: &str) -> bool {
+    let module = module.trim().trim_end_matches('/');
+    if module.is_empty() {
+        return false;
+    }
+    path == module
+        || Path::new(path).starts_with(module)
+        || Pattern::new(module).is_ok_and(|pattern| pattern.matches_with(path, MATCH_OPTIONS))
+}
+
+/// Check if a changed path matches one of the `ignore` glob patterns
+fn is_ignored(path: &str, ignore: &[Pattern]) -> bool {
+    ignore
+        .iter()
+        .any(|pattern| pattern.matches_with(path, MATCH_OPTIONS))
+}
+
+pub fn check_changesets(matches: &ArgMatches, config: &Config) -> Result<(), VersionWiseError> {
+    // Get the base reference. The CLI flag has priority over the configuration
+    let base = match matches.get_one::<String>("base") {
+        Some(base) => base.clone(),
+        None => config.base_ref.clone().unwrap_or(DEFAULT_BASE.to_string()),
+    };
+    // Get the files that this branch changes
+    let changed_files = get_git_changed_files(Some(&base))?;
+    // Look for new changesets. Editing an existing one doesn't count, and they
+    // should exist, since a deleted changeset doesn't count either.
+    // The archived ones are in a subfolder, and they don't count either
+    let in_changeset_dir = |file: &str| Path::new(file).starts_with(&config.changeset_dir);
+    let has_new_changeset = get_git_added_files(&base)?.iter().any(|file| {
+        Path::new(file).parent() == Some(Path::new(&config.changeset_dir))
+            && file.ends_with(".toml")
+            && Path::new(file).file_name() != Some(PRE_STATE_FILE.as_ref())
+            && Path::new(file).is_file()
+    });
+    // The CHANGELOG files are written by versionwise itself (like the
+    // Unreleased section of `create`), so they don't require a changeset
+    let changelogs: Vec<&Path> = std::iter::once(&config.changelog)
+        .chain(config.changelog_outputs.iter().map(|output| &output.path))
+        .map(|path| Path::new(path.strip_prefix("./").unwrap_or(path)))
+        .collect();
+    let is_changelog = |file: &str| changelogs.contains(&Path::new(file));
+    // And the source files that require a changeset
+    let source_files: Vec<&String> = changed_files
+        .iter()
+        .filter(|file| !in_changeset_dir(file) && !is_changelog(file))
+        .filter(|file| !is_ignored(file, &config.ignore))
+        .collect();
+
+    if source_files.is_empty() {
+        println!("No source files changed against `{}`.", base);
+        return Ok(());
+    }
+    // Find the changed files that are not covered by any changeset module
+    let changesets = get_current_changesets(config)?;
+    let uncovered: Vec<&&String> = source_files
+        .iter()
+        .filter(|file| {
+            !changesets.iter().any(|changeset| {
+                changeset
+                    .modules
+                    .split(", ")
+                    .any(|module| is_covered(file, module))
+            })
+        })
+        .collect();
+    // Print them, if there's any
+    if !uncovered.is_empty() {
+        println!("Changed files not covered by any changeset module:");
+        for file in &uncovered {
+            println!("    - {}", file.yellow());
+        }
+    }
+    // And fail if there's no new changeset at all
+    if !has_new_changeset {
+        return Err(VersionWiseError::Check(format!(
+            "{} source file(s) changed against `{}`, but no changeset was added in `{}/`. Create one with `versionwise create`.",
+            source_files.len(),
+            base,
+            config.changeset_dir
+        )));
+    }
+    if matches.get_flag("strict") && !uncovered.is_empty() {
+        return Err(VersionWiseError::Check(format!(
+            "{} changed file(s) are not covered by any changeset module.",
+            uncovered.len()
+        )));
+    }
+    println!(
+        "{} The changes of this branch have a changeset.",
+        "✔".green()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modules_cover_files_folders_and_globs() {
+        let covered = [
+            ("src/api.rs", "src/api.rs"),
+            ("src/api/routes.rs", "src/api"),
+            ("src/api/routes.rs", "src/api/"),
+            ("src/api/routes.rs", "src/*/routes.rs"),
+            ("src/api/routes.rs", "src/**/*.rs"),
+        ];
+        for (path, module) in covered {
+            assert!(is_covered(path, module), "{} covers {}", module, path);
+        }
+        let not_covered = [
+            ("src/api.rs", ""),
+            ("src/api.rs", "src/api"),
+            ("src/api_v2/routes.rs", "src/api"),
+            ("src/api/routes.rs", "src/*.rs"),
+        ];
+        for (path, module) in not_covered {
+            assert!(
+                !is_covered(path, module),
+                "{} doesn't cover {}",
+                module,
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn ignores_the_matching_files() {
+        let ignore: Vec<Pattern> = ["docs/**", "**/*.md", "tests/*"]
+            .iter()
+            .map(|glob| Pattern::new(glob).unwrap())
+            .collect();
+        for path in [
+            "docs/index.html",
+            "docs/api/intro.rst",
+            "README.md",
+            "src/NOTES.md",
+        ] {
+            assert!(is_ignored(path, &ignore), "{}", path);
+        }
+        for path in ["src/docs.rs", "tests/unit/a.rs", "README.txt"] {
+            assert!(!is_ignored(path, &ignore), "{}", path);
+        }
+        assert!(!is_ignored("README.md", &[]));
+    }
+}
@@ -6,19 +6,22 @@
 /// * P2: Select the type of versioning change (major, minor, patch)
 /// * P3: Search for the available modules in the package. If not found, let them write their own module name
 /// * P4: Write the message to add in the changeset
+use clap::ArgMatches;
 use colored::*;
 use fake::faker::lorem::en::Word;
 use fake::Fake;
-use regex::Regex;
-use requestty::{prompt, prompt_one, Answer, Question};
+use requestty::{prompt_one, Answer, Question};
 use std::fs;
+use std::io::{self, IsTerminal};
 use std::path::Path;
-use std::process::Command;
 // Local imports
 use crate::options::Changeset;
+use crate::utilities::config::TagConfig;
+use crate::utilities::pre_release::PRE_STATE_FILE;
+use crate::utilities::version_operations::{calculate_next_version, ChangeType};
 use crate::utilities::{
-    create_changeset_folder, find_version, generate_ai_message,
-    version_operations::calculate_next_version, write_changeset_file, AIConfig,
+    create_changeset_folder, find_version, generate_ai_message, get_git_changed_files,
+    open_changelog, update_unreleased, write_changeset_file, AIConfig, Config, VersionWiseError,
 };
 
 /// Detect modules in the project by scanning files
@@ -54,50 +57,14 @@ fn detect_modules() -> Vec<String> {
     modules
 }
 
-/// Get default message template based on change type and tag
-fn get_message_template(change_type: &str, tag: &str) -> String {
-    match (change_type, tag) {
-        ("MAJOR", "Remove") => "Remove ... functionality because ...".to_string(),
-        ("MAJOR", "Rename") => "Rename ... to ... to better reflect ...".to_string(),
-        ("MAJOR", "I/O") => "Change ... input/output to ...".to_string(),
-        ("MAJOR", "Behavior") => "Change behavior of ... to ...".to_string(),
-
-        ("MINOR", "Feature") => "Add ... feature that allows ...".to_string(),
-        ("MINOR", "Add") => "Add ... functionality to ...".to_string(),
-        ("MINOR", "I/O") => "Include optional ... parameter to ...".to_string(),
-        ("MINOR", "Deprecated") => {
-            "Mark ... as deprecated, to be removed in version ...".to_string()
-        }
-
-        ("PATCH", "Refactor") => "Refactor ... to improve ...".to_string(),
-        ("PATCH", "Bug") => "Fix ... bug where ...".to_string(),
-        ("PATCH", "Optimization") => "Optimize ... to improve performance by ...".to_string(),
-        ("PATCH", "Tests") => "Add tests for ... to verify ...".to_string(),
-        ("PATCH", "Patch") => "Update ... to handle ...".to_string(),
-
-        _ => "".to_string(),
-    }
-}
-
-/// Get changed files from git
-fn get_git_changed_files() -> Vec<String> {
-    let mut changed_files = Vec::new();
-
-    // Try to get modified files from git
-    let output = Command::new("git")
-        .args(["diff", "--name-only", "HEAD"])
-        .output();
-
-    if let Ok(output) = output {
-        if output.status.success() {
-            let git_output = String::from_utf8_lossy(&output.stdout);
-            for line in git_output.lines() {
-                if !line.is_empty() {
-                    changed_files.push(line.to_string());
-                }
-            }
-        }
-    }
+/// Get the changed files from git as module choices.
+/// Outside of a git repository there are no choices.
+fn git_module_choices(config: &Config) -> Vec<String> {
+    let mut changed_files: Vec<String> = get_git_changed_files(None)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|file| !Path::new(file).starts_with(&config.changeset_dir))
+        .collect();
 
     // Add "Other" option at the end
     if !changed_files.is_empty() {
@@ -107,94 +74,143 @@ fn get_git_changed_files() -> Vec<String> {
     changed_files
 }
 
-/// Select tags depending on the change type
-fn select_tags(change_type: &str) -> Vec<String> {
-    let available_tags: Vec<String>;
-    // Based on the change type representation, select the tags.
-    if change_type == "MAJOR" {
-        available_tags = vec![
-            String::from("⚰️  Remove: Removed features."),
-            String::from("🚚 Rename: Renamed features."),
-            String::from("✏️  I/O: Changing input/output of features."),
-            String::from("💥 Behavior: Changing features behavior."),
-        ];
-    } else if change_type == "MINOR" {
-        available_tags = vec![
-            String::from("✨ Feature: New feature."),
-            String::from("➕ Add: Add functionality to existing feature."),
-            String::from("✏️  I/O: Include optional input/output to a feature."),
-            String::from("🗑️  Deprecated: Deprecated features."),
-        ];
-    } else {
-        available_tags = vec![
-            String::from("♻️  Refactor: Refactor of existing code."),
-            String::from("🐛 Bug: Fix a bug."),
-            String::from("⚡️ Optimization: Simple optimization of code."),
-            String::from("🧪 Tests: Include or update tests."),
-            String::from("🩹 Patch: Include or delete logs, catch errors or related things."),
-        ];
+/// Select the configured tags of a change type
+fn select_tags<'a>(
+    config: &'a Config,
+    change_type: &str,
+) -> Result<Vec<&'a TagConfig>, VersionWiseError> {
+    let change: ChangeType = change_type.parse()?;
+    let tags = config.tags_for(change);
+    if tags.is_empty() {
+        return Err(VersionWiseError::Config(format!(
+            "There are no `tags` configured for a {} change.",
+            change
+        )));
+    }
+    Ok(tags)
+}
+
+/// Validate a tag given through the CLI against the available tags for the change type.
+/// The match is case insensitive, and the configured tag is returned.
+fn validate_tag<'a>(
+    config: &'a Config,
+    change_type: &str,
+    tag: &str,
+) -> Result<&'a TagConfig, VersionWiseError> {
+    let available_tags = select_tags(config, change_type)?;
+    // Search for the tag, ignoring the case
+    match available_tags
+        .iter()
+        .find(|available| available.name.eq_ignore_ascii_case(tag.trim()))
+    {
+        Some(available) => Ok(available),
+        None => Err(VersionWiseError::Input(format!(
+            "The tag `{}` is not valid for a {} change. Available tags: {}.",
+            tag,
+            change_type,
+            available_tags
+                .iter()
+                .map(|available| available.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        ))),
     }
-    // Return the selected tags
-    available_tags
 }
 
 /// Create the question to set the tag
-fn set_tag(change_type: &str) -> String {
+fn set_tag<'a>(config: &'a Config, change_type: &str) -> Result<&'a TagConfig, VersionWiseError> {
     // Get the available tags
-    let available_tags = select_tags(change_type);
+    let available_tags = select_tags(config, change_type)?;
     // Create the question
     let tag_question = Question::select("tag")
         .message("Select the tag for this change")
-        .choices(available_tags)
+        .choices(available_tags.iter().map(|tag| tag.label()))
         .build();
     // Perform the question
-    let results = prompt_one(tag_question);
-    let result = match results {
-        // Check and receive the OK, if there's any
-        Ok(x) => x,
-        Err(_) => panic!("There's something wrong selecting the tag for the changeset"),
-    };
-    // And, at the end, just receive the answer
-    let mut tag = result.as_list_item().unwrap().text.as_str();
-    // And now, clean the tag
-    let re = Regex::new(r"([A-Za-z]+):").unwrap();
-    if let Some(capture) = re.captures(tag) {
-        if let Some(matched) = capture.get(1) {
-            tag = matched.as_str();
-        }
+    let result = prompt_one(tag_question)?;
+    // And, at the end, just receive the answer and find its tag
+    Ok(available_tags[result.as_list_item().unwrap().index])
+}
+
+/// Validate the changeset name. It's used as the file name, so it can't
+/// point to another folder, nor replace the pre-release state
+fn validate_name(name: &str) -> Result<String, VersionWiseError> {
+    let name = name.trim();
+    if name.contains(['/', '\\']) || name.contains("..") {
+        return Err(VersionWiseError::Input(format!(
+            "The changeset name `{}` is not valid. It cannot contain `/`, `\\` or `..`.",
+            name
+        )));
     }
-    tag.to_string()
+    if PRE_STATE_FILE
+        .strip_suffix(".toml")
+        .is_some_and(|reserved| name.eq_ignore_ascii_case(reserved))
+    {
+        return Err(VersionWiseError::Input(format!(
+            "The changeset name `{}` is reserved for the pre-release state. Use another name.",
+            name
+        )));
+    }
+    Ok(name.to_string())
 }
 
-/// Create the questions
-fn create_questions(default_name: &str) -> Vec<Question<'static>> {
+/// Ask for the changeset name. If it is left blank, a random name is used.
+fn ask_for_name() -> Result<String, VersionWiseError> {
+    // Generate the default name
+    let default_name = "Leave it blank for a random name";
     //* P1
     let changeset_name: Question<'_> = Question::input("name")
         .message("Write the Changeset name")
         .default(default_name)
         .build();
+    // Get the result
+    let result = prompt_one(changeset_name)?;
+    let name = result.as_string().unwrap();
+    if name == default_name || name.trim().is_empty() {
+        Ok(Word().fake())
+    } else {
+        Ok(name.to_string())
+    }
+}
+
+/// Ask for the change type. Only the change types with configured tags are offered
+fn ask_for_change_type(config: &Config) -> Result<String, VersionWiseError> {
     //* P2
-    let change_type: Question<'_> = Question::select("change_type")
-        .message("Select the change type that is most adequate to these changes")
-        .choices(vec![
+    let change_types: Vec<(ChangeType, &str)> = [
+        (
+            ChangeType::Major,
             "💥 MAJOR: Most of the time related to breaking changes.",
+        ),
+        (
+            ChangeType::Minor,
             "✨ MINOR: New features that keep backwards compatibility.",
+        ),
+        (
+            ChangeType::Patch,
             "🩹 PATCH: Refactors, bugs, fixes and small changes.",
-        ])
+        ),
+        (
+            ChangeType::None,
+            "📝 NONE: Internal changes that don't bump the version.",
+        ),
+    ]
+    .into_iter()
+    .filter(|(change, _)| !config.tags_for(*change).is_empty())
+    .collect();
+    let change_type: Question<'_> = Question::select("change_type")
+        .message("Select the change type that is most adequate to these changes")
+        .choices(change_types.iter().map(|(_, label)| *label))
         .build();
-
-    // Return the initial questions - module and message will be asked later
-    // after we know the change type and tag
-    let questions = vec![changeset_name, change_type];
-
-    // Return the questions
-    questions
+    // Get the result
+    let result = prompt_one(change_type)?;
+    let (change, _) = change_types[result.as_list_item().unwrap().index];
+    Ok(change.to_string())
 }
 
 /// Ask for module based on git changes and auto-detected modules
-fn ask_for_module() -> String {
+fn ask_for_module(config: &Config) -> Result<String, VersionWiseError> {
     // First try to get git changed files
-    let git_modules = get_git_changed_files();
+    let git_modules = git_module_choices(config);
 
     let module_question: Question;
 
@@ -224,7 +240,7 @@ fn ask_for_module() -> String {
     }
 
     // Get the answer
-    let result = prompt_one(module_question).expect("Error getting module input");
+    let result = prompt_one(module_question)?;
 
     // Handle the result based on whether it was a select or input
     let module = match result {
@@ -234,7 +250,7 @@ fn ask_for_module() -> String {
                 let custom_module = Question::input("custom_module")
                     .message("Enter the custom module name")
                     .build();
-                let custom_result = prompt_one(custom_module).expect("Error getting custom module");
+                let custom_result = prompt_one(custom_module)?;
                 custom_result.as_string().unwrap().to_string()
             } else {
                 item.text
@@ -244,11 +260,11 @@ fn ask_for_module() -> String {
         _ => "".to_string(),
     };
 
-    module
+    Ok(module)
 }
 
 /// Ask for message generation method (AI, template, manual)
-fn ask_for_message_method() -> String {
+fn ask_for_message_method() -> Result<String, VersionWiseError> {
     let method_question = Question::select("message_method")
         .message("How would you like to create your changeset message?")
         .choices(vec![
@@ -258,30 +274,37 @@ fn ask_for_message_method() -> String {
         ])
         .build();
 
-    let result = prompt_one(method_question).expect("Error selecting message method");
-    result.as_list_item().unwrap().text.to_string()
+    let result = prompt_one(method_question)?;
+    Ok(result.as_list_item().unwrap().text.to_string())
 }
 
 /// Ask for the message with template suggestions
-fn ask_for_message(change_type: &str, tag: &str, module: &str) -> String {
+fn ask_for_message(
+    config: &Config,
+    change_type: &str,
+    tag: &TagConfig,
+    module: &str,
+) -> Result<String, VersionWiseError> {
     // First, ask which method to use
-    let method = ask_for_message_method();
+    let method = ask_for_message_method()?;
 
     if method.contains("Generate with AI") {
         // Create AI configuration using build method
-        let config = AIConfig::build();
+        let ai_config = AIConfig::build(&config.ai)?;
 
         // Generate a message with AI
         println!("Analyzing changes and generating message...");
 
         // We need to block on the async call since we're in a sync context
         let ai_message = tokio::runtime::Runtime::new()
-            .unwrap()
-            .block_on(generate_ai_message(change_type, tag, module, &config))
-            .unwrap_or_else(|e| {
-                println!("Error generating AI message: {}", e);
-                "Error generating message".to_string()
-            });
+            .map_err(|e| VersionWiseError::Ai(format!("Cannot start the async runtime: {}", e)))?
+            .block_on(generate_ai_message(
+                change_type,
+                &tag.name,
+                module,
+                &ai_config,
+            ))
+            .map_err(VersionWiseError::Ai)?;
 
         // Ask if user wants to edit the generated message
         let edit_question = Question::confirm("edit_message")
@@ -292,7 +315,7 @@ fn ask_for_message(change_type: &str, tag: &str, module: &str) -> String {
             .default(false)
             .build();
 
-        let edit_result = prompt_one(edit_question).expect("Error asking to edit message");
+        let edit_result = prompt_one(edit_question)?;
 
         if edit_result.as_bool().unwrap() {
             // User wants to edit the message
@@ -301,37 +324,37 @@ fn ask_for_message(change_type: &str, tag: &str, module: &str) -> String {
                 .default(&ai_message)
                 .build();
 
-            let edited_result = prompt_one(edit_message_question).expect("Error editing message");
-            edited_result.as_string().unwrap().to_string()
+            let edited_result = prompt_one(edit_message_question)?;
+            Ok(edited_result.as_string().unwrap().to_string())
         } else {
             // Use the AI message as is
-            ai_message
+            Ok(ai_message)
         }
     } else if method.contains("Use message template") {
         // Use template approach
-        let template = get_message_template(change_type, tag);
+        let template = &tag.template;
 
         let message_question = Question::input("message")
             .message("Write the message for the change")
-            .default(&template)
+            .default(template)
             .build();
 
-        let result = prompt_one(message_question).expect("Error getting message input");
+        let result = prompt_one(message_question)?;
         let mut message: String = result.as_string().unwrap().to_string();
 
-        while message.is_empty() || message == template {
+        while message.is_empty() || message == *template {
             println!(
                 "Error: You need to add a personalized message. The template cannot be used as is."
             );
             let retry_question = Question::input("message")
                 .message("Write the message for the change")
-                .default(&template)
+                .default(template)
                 .build();
-            let retry_result = prompt_one(retry_question).expect("Error getting message input");
+            let retry_result = prompt_one(retry_question)?;
             message = retry_result.as_string().unwrap().to_string();
         }
 
-        message.to_string()
+        Ok(message.to_string())
     } else {
         // Write from scratch
         let message_question = Question::input("message")
@@ -339,19 +362,21 @@ fn ask_for_message(change_type: &str, tag: &str, module: &str) -> String {
             .default("")
             .build();
 
-        let result = prompt_one(message_question).expect("Error getting message input");
+        let result = prompt_one(message_question)?;
         let message = result.as_string().unwrap();
 
         if message.is_empty() {
-            panic!("There was no message for the changeset. You need to add a message.");
+            return Err(VersionWiseError::Input(
+                "There was no message for the changeset. You need to add a message.".to_string(),
+            ));
         }
 
-        message.to_string()
+        Ok(message.to_string())
     }
 }
 
 /// Display a summary and confirm before saving
-fn confirm_changeset(changeset: &Changeset) -> bool {
+fn confirm_changeset(changeset: &Changeset) -> Result<bool, VersionWiseError> {
     println!("\n{}", "Changeset Summary:".bold());
     println!("Name: {}.toml", changeset.name);
     println!("Type: {}", changeset.change);
@@ -369,90 +394,150 @@ fn confirm_changeset(changeset: &Changeset) -> bool {
         .default(true)
         .build();
 
-    let result = prompt_one(confirm_question).expect("Error getting confirmation");
-    result.as_bool().unwrap()
+    let result = prompt_one(confirm_question)?;
+    Ok(result.as_bool().unwrap())
 }
 
-fn process_answers() -> Changeset {
-    // Generate the default name
-    let default_name = "Leave it blank for a random name";
-    // Process the initial results (name and change type)
-    let results = prompt(create_questions(default_name));
-    // Get the results
-    let result = match results {
-        // Check and receive the OK, if there's any
-        Ok(x) => x,
-        Err(_) => panic!("There's something wrong creating the changeset"),
-    };
+/// Values given through the CLI flags of the `create` command.
+/// Every field that is `None` is asked interactively.
+struct CreateArgs {
+    name: Option<String>,
+    change_type: Option<String>,
+    tag: Option<String>,
+    modules: Option<Vec<String>>,
+    message: Option<String>,
+    yes: bool,
+}
 
-    // Get the name
-    let mut name = result.get("name").unwrap().as_string().unwrap();
-    if name == default_name || name.trim().is_empty() {
-        name = Word().fake();
+impl CreateArgs {
+    fn from_matches(matches: &ArgMatches) -> CreateArgs {
+        CreateArgs {
+            name: matches.get_one::<String>("name").cloned(),
+            change_type: matches
+                .get_one::<String>("type")
+                .map(|change| change.to_uppercase()),
+            tag: matches.get_one::<String>("tag").cloned(),
+            modules: matches
+                .get_many::<String>("module")
+                .map(|modules| modules.cloned().collect()),
+            message: matches.get_one::<String>("message").cloned(),
+            yes: matches.get_flag("yes"),
+        }
     }
+}
+
+/// Error returned when a required field is missing and we cannot prompt for it
+fn missing_field(flag: &str) -> VersionWiseError {
+    VersionWiseError::Input(format!(
+        "Missing `{}`. The standard input is not a terminal, so it cannot be asked interactively.",
+        flag
+    ))
+}
+
+/// Build the changeset from the CLI flags and the answers of the prompts.
+/// Returns `None` if the user cancels the creation.
+fn process_answers(
+    args: CreateArgs,
+    config: &Config,
+) -> Result<Option<Changeset>, VersionWiseError> {
+    // Only ask questions if there's someone to answer them
+    let interactive = io::stdin().is_terminal();
+
+    // Get the name. Without a terminal, a random one is used
+    let name = match args.name {
+        Some(name) if !name.trim().is_empty() => validate_name(&name)?,
+        None if interactive => validate_name(&ask_for_name()?)?,
+        _ => Word().fake(),
+    };
 
     // Get the change type
-    let mut change = result
-        .get("change_type")
-        .unwrap()
-        .as_list_item()
-        .unwrap()
-        .text
-        .as_str();
-    // Instance the regex to search for the word
-    let re = Regex::new(r"\b(MAJOR|MINOR|PATCH)\b").unwrap();
-    // get the change
-    if let Some(capture) = re.captures(change) {
-        if let Some(matched) = capture.get(1) {
-            change = matched.as_str();
-        }
-    }
+    let change = match args.change_type {
+        Some(change) => change.parse::<ChangeType>()?.to_string(),
+        None if interactive => ask_for_change_type(config)?,
+        None => return Err(missing_field("--type")),
+    };
 
     // Get the tag (now that we know the change type)
-    let tag = set_tag(change);
+    let tag = match args.tag {
+        Some(tag) => validate_tag(config, &change, &tag)?,
+        None if interactive => set_tag(config, &change)?,
+        None => return Err(missing_field("--tag")),
+    };
 
-    // Get the module (with git and auto-detection)
-    let module = ask_for_module();
+    // Get the module (with git and auto-detection). It is optional.
+    let module = match args.modules {
+        Some(modules) => modules.join(", "),
+        None if interactive => ask_for_module(config)?,
+        None => String::new(),
+    };
 
     // Get the message (with AI, templates, or manual input)
-    let message = ask_for_message(change, &tag, &module);
+    let message = match args.message {
+        Some(message) if !message.trim().is_empty() => message,
+        Some(_) => {
+            return Err(VersionWiseError::Input(
+                "The changeset message cannot be empty.".to_string(),
+            ))
+        }
+        None if interactive => ask_for_message(config, &change, tag, &module)?,
+        None => return Err(missing_field("--message")),
+    };
 
     // Get the current version
-    let current_version = find_version();
+    let current_version = find_version(config)?;
 
     // Calculate the next version based on the change type
-    let next_version = calculate_next_version(&current_version, change);
+    let next_version = calculate_next_version(&current_version, &change, &config.version_scheme)?;
 
     // Create the changeset
     let changeset = Changeset {
-        name: name.into(),
-        change: change.into(),
+        name,
+        change,
         modules: module,
-        tag,
+        tag: tag.name.clone(),
         message,
         version: next_version,
     };
 
     // Return the changeset only if confirmed
-    if confirm_changeset(&changeset) {
-        changeset
+    if args.yes {
+        Ok(Some(changeset))
+    } else if !interactive {
+        Err(VersionWiseError::Input(
+            "Cannot confirm the changeset without a terminal. Use `--yes` to save it directly."
+                .to_string(),
+        ))
+    } else if confirm_changeset(&changeset)? {
+        Ok(Some(changeset))
     } else {
-        println!("Changeset creation cancelled.");
-        std::process::exit(0);
+        Ok(None)
     }
 }
 
-pub fn create_changesets() {
+pub fn create_changesets(matches: &ArgMatches, config: &Config) -> Result<(), VersionWiseError> {
     // Process the results
-    let changeset: Changeset = process_answers();
+    let Some(changeset) = process_answers(CreateArgs::from_matches(matches), config)? else {
+        println!("Changeset creation cancelled.");
+        return Ok(());
+    };
+    // The CHANGELOG must be readable before the changeset is saved, so a
+    // missing one doesn't leave the changeset written and the command failed
+    if config.unreleased {
+        open_changelog(config)?;
+    }
     // Then, start creating the Changeset file in the changeset function
     // Let's see if the folder exists. If not, create it
-    create_changeset_folder();
+    create_changeset_folder(config)?;
     // Once you have created the folder, create the changeset
-    write_changeset_file(&changeset);
+    write_changeset_file(config, &changeset)?;
+    // And keep the Unreleased section of the CHANGELOG up to date
+    if config.unreleased {
+        update_unreleased(config)?;
+    }
     // Once you have created it, print a confirmation message
     println!(
         "\n Changeset `{}.toml` has been created! 🎉",
         changeset.name.green()
     );
+    Ok(())
 }
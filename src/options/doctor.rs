@@ -0,0 +1,95 @@
+/// ================================ ///
+///         OPTIONS :: Doctor        ///
+/// ================================ ///
+/// Check that every version file has the same version, and align them with `--fix`.
+use clap::ArgMatches;
+use colored::*;
+use requestty::{prompt_one, Question};
+use std::io::{self, IsTerminal};
+// Local imports
+use crate::utilities::{
+    read_versions, update_version_path, versions_match, Config, VersionScheme, VersionWiseError,
+};
+
+/// Print the version of each file, marking the ones that don't match the first one
+fn print_versions(
+    versions: &[(String, String)],
+    scheme: &VersionScheme,
+) -> Result<(), VersionWiseError> {
+    let expected = scheme.normalize(&versions[0].1)?;
+    // Align the columns with the longest path and version
+    let path_width = versions
+        .iter()
+        .map(|(p, _)| p.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    let version_width = versions
+        .iter()
+        .map(|(_, v)| v.len())
+        .max()
+        .unwrap_or(0)
+        .max(7);
+    println!(
+        "{:<path_width$}  {:<version_width$}  {}",
+        "File".bold(),
+        "Version".bold(),
+        "Status".bold()
+    );
+    for (path, version) in versions {
+        let status = match scheme.normalize(version)? == expected {
+            true => "ok".green(),
+            false => "mismatch".red(),
+        };
+        println!(
+            "{:<path_width$}  {:<version_width$}  {}",
+            path, version, status
+        );
+    }
+    Ok(())
+}
+
+/// Ask which of the found versions should be written in every file
+fn ask_for_version(versions: &[(String, String)]) -> Result<String, VersionWiseError> {
+    let choices: Vec<String> = versions
+        .iter()
+        .map(|(path, version)| format!("{} ({})", version, path))
+        .collect();
+    let question = Question::select("version")
+        .message("Select the version to write in every version file")
+        .choices(choices)
+        .build();
+    let result = prompt_one(question)?;
+    let index = result.as_list_item().unwrap().index;
+    Ok(versions[index].1.clone())
+}
+
+pub fn doctor(matches: &ArgMatches, config: &Config) -> Result<(), VersionWiseError> {
+    let scheme = &config.version_scheme;
+    // Read and show the version of every file
+    let versions = read_versions(config)?;
+    print_versions(&versions, scheme)?;
+    let consistent = versions_match(&versions, scheme)?;
+    // Without `--fix`, just report it
+    if matches.value_source("fix").is_none() {
+        if consistent {
+            println!("\nAll the version files are at v{}.", versions[0].1.blue());
+            return Ok(());
+        }
+        return Err(VersionWiseError::Version(
+            "The version files are not consistent. Run `versionwise doctor --fix` to align them."
+                .to_string(),
+        ));
+    }
+    // Find the version to write
+    let version = match matches.get_one::<String>("fix") {
+        Some(version) => version.clone(),
+        None if consistent => versions[0].1.clone(),
+        None if io::stdin().is_terminal() => ask_for_version(&versions)?,
+        None => versions[0].1.clone(),
+    };
+    scheme.normalize(&version)?;
+    update_version_path(config, &version)?;
+    println!("\nAll the version files are now at v{}.", version.blue());
+    Ok(())
+}
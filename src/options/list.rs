@@ -1,50 +1,163 @@
 /// ================================ ///
 ///         OPTIONS :: List          ///
 /// ================================ ///
+use clap::ArgMatches;
 use colored::*;
-use std::collections::HashSet;
+use serde::Serialize;
 // Local imports
-use crate::utilities::{find_largest_version, get_current_changesets};
+use crate::options::Changeset;
+use crate::utilities::config::NoBumpRelease;
+use crate::utilities::{
+    find_version, get_current_changesets, group_by_section, new_changelog_entry, next_release,
+    read_pre_state, Config, PreMode, PreState, VersionWiseError,
+};
 
-pub fn list_changesets() {
-    // Get the changesets and list them
-    let changesets = get_current_changesets();
-    // Find the current project version
-    let new_version = find_largest_version(&changesets).unwrap();
-    // Print the new version to set with these changesets
-    println!("# New version to be bumped: v{}.\n", new_version.blue());
-    // Add a vec of tags that were visited
-    let mut printed_tags: HashSet<&String> = HashSet::new();
-    // Process them
-    for change_type in ["MAJOR", "MINOR", "PATCH"] {
-        // Print the first layer
+/// Stable schema of the machine-readable output of `list`
+#[derive(Serialize)]
+struct ListOutput<'a> {
+    current_version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pre_release: Option<&'a PreState>,
+    changesets: Vec<ChangesetOutput<'a>>,
+}
+
+/// A single pending changeset in the machine-readable output of `list`
+#[derive(Serialize)]
+struct ChangesetOutput<'a> {
+    name: &'a str,
+    change_type: &'a str,
+    tag: &'a str,
+    modules: Vec<&'a str>,
+    message: &'a str,
+    path: String,
+}
 
+impl<'a> ChangesetOutput<'a> {
+    fn new(config: &Config, changeset: &'a Changeset) -> Self {
+        ChangesetOutput {
+            name: &changeset.name,
+            change_type: changeset.change.trim(),
+            tag: &changeset.tag,
+            modules: changeset
+                .modules
+                .split(", ")
+                .filter(|module| !module.is_empty())
+                .collect(),
+            message: &changeset.message,
+            path: config.changeset_path(&format!("{}.toml", changeset.name)),
+        }
+    }
+}
+
+/// Print the changesets in a machine-readable format
+fn print_formatted(
+    config: &Config,
+    format: &str,
+    changesets: &[Changeset],
+    next_version: Option<String>,
+    pre_state: Option<&PreState>,
+) -> Result<(), VersionWiseError> {
+    // The markdown is the same entry that `bump` would write
+    if format == "markdown" {
+        if let Some(version) = next_version {
+            print!(
+                "{}",
+                new_changelog_entry(config, changesets, &version)?.join("")
+            );
+        }
+        return Ok(());
+    }
+    // Build the output
+    let output = ListOutput {
+        current_version: find_version(config)?,
+        next_version,
+        pre_release: pre_state,
+        changesets: changesets
+            .iter()
+            .map(|changeset| ChangesetOutput::new(config, changeset))
+            .collect(),
+    };
+    // And serialize it
+    let serialized = match format {
+        "json" => serde_json::to_string_pretty(&output).map_err(|e| e.to_string()),
+        _ => toml::to_string(&output).map_err(|e| e.to_string()),
+    }
+    .map_err(|e| VersionWiseError::parse(format!("<{} output>", format), e))?;
+    println!("{}", serialized);
+    Ok(())
+}
+
+pub fn list_changesets(matches: &ArgMatches, config: &Config) -> Result<(), VersionWiseError> {
+    // Get the changesets and the release that they produce
+    let pre_state = read_pre_state(config)?;
+    let release = next_release(config, get_current_changesets(config)?)?;
+    let bumps_version = release
+        .as_ref()
+        .is_some_and(|release| release.bumps_version);
+    let (next_version, changesets) = match release {
+        // Changesets that don't bump the version may not be released at all
+        Some(release) if !bumps_version && config.no_bump.release == NoBumpRelease::Skip => {
+            (None, release.changesets)
+        }
+        Some(release) => (Some(release.version), release.changesets),
+        None => (None, Vec::new()),
+    };
+    // If a format was requested, use it
+    let format = matches.get_one::<String>("format").unwrap();
+    if format != "text" {
+        return print_formatted(
+            config,
+            format,
+            &changesets,
+            next_version,
+            pre_state.as_ref(),
+        );
+    }
+    // Show the pre-release mode, if any
+    match &pre_state {
+        Some(state) if state.mode == PreMode::Pre => println!(
+            "# Pre-release mode `{}`: only the changesets not released yet are included.",
+            state.tag.yellow()
+        ),
+        Some(state) => println!(
+            "# Leaving the pre-release mode `{}`: every changeset of the cycle is included.",
+            state.tag.yellow()
+        ),
+        None => {}
+    }
+    // Print the new version to set with these changesets
+    match next_version {
+        _ if changesets.is_empty() => {
+            println!("There are no pending changesets.");
+            return Ok(());
+        }
+        Some(new_version) if bumps_version => {
+            println!("# New version to be bumped: v{}.\n", new_version.blue())
+        }
+        Some(new_version) => println!(
+            "# No version bump. The changesets will be recorded under v{}.\n",
+            new_version.blue()
+        ),
+        None => println!("# No version bump. The changesets will wait for the next release.\n"),
+    }
+    // Process them by change type, and then by CHANGELOG section
+    for change_type in ["MAJOR", "MINOR", "PATCH", "NONE"] {
         // Filter the changesets for all those that match the change type
-        for changeset in changesets.iter().filter(|c| c.change.trim() == change_type) {
-            // If this tag has been printed already, then continue
-            if printed_tags.contains(&changeset.tag) {
-                continue;
-            }
-            // Print the tag if has not been printed already
-            println!("- [{}]", changeset.tag.green());
-            // And now, filter all the changesets for the tag and the change type
-            for nested_changeset in changesets
-                .iter()
-                .filter(|c| c.change.trim() == change_type && c.tag == changeset.tag)
-            {
+        let of_change_type = changesets.iter().filter(|c| c.change.trim() == change_type);
+        for (section, grouped) in group_by_section(config, of_change_type) {
+            // Print the section
+            println!("- [{}]", section.green());
+            for changeset in grouped {
                 // If this changeset has a module, include it. If not, then just don't
-                if nested_changeset.modules.is_empty() {
-                    println!("    - {}", nested_changeset.message);
+                if changeset.modules.is_empty() {
+                    println!("    - {}", changeset.message);
                 } else {
-                    println!(
-                        "    - {}: {}",
-                        nested_changeset.modules.blue(),
-                        nested_changeset.message
-                    );
+                    println!("    - {}: {}", changeset.modules.blue(), changeset.message);
                 }
             }
-            // And include this tag in the printed ones
-            printed_tags.insert(&changeset.tag);
         }
     }
+    Ok(())
 }
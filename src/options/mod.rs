@@ -1,10 +1,20 @@
 // Import the files
 mod bump;
+mod changelog;
 mod changeset;
+mod check;
 mod create;
+mod doctor;
 mod list;
+mod notes;
+mod pre;
 // Make them public
 pub use bump::bump_version;
+pub use changelog::changelog;
 pub use changeset::Changeset;
+pub use check::check_changesets;
 pub use create::create_changesets;
+pub use doctor::doctor;
 pub use list::list_changesets;
+pub use notes::release_notes;
+pub use pre::pre_release;
@@ -0,0 +1,219 @@
+/// ================================ ///
+///         OPTIONS :: Notes         ///
+/// ================================ ///
+/// Extract the release notes of one or several versions from the CHANGELOG,
+/// to publish them in the release of the Git host.
+use clap::ArgMatches;
+use regex::Regex;
+use serde::Serialize;
+use std::ops::Range;
+use std::sync::OnceLock;
+// Local imports
+use crate::utilities::changelog::{Changelog, Release};
+use crate::utilities::{open_changelog, Config, VersionWiseError};
+
+/// Stable schema of a release in the JSON output of `notes`
+#[derive(Serialize)]
+struct ReleaseOutput<'a> {
+    version: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date: Option<&'a str>,
+    sections: Vec<SectionOutput>,
+    /// Markdown of the release, without its heading
+    body: String,
+}
+
+/// A section of a release in the JSON output of `notes`
+#[derive(Serialize)]
+struct SectionOutput {
+    name: String,
+    items: Vec<String>,
+}
+
+impl<'a> ReleaseOutput<'a> {
+    fn new(release: &'a Release) -> Self {
+        ReleaseOutput {
+            version: &release.version,
+            date: release.date.as_deref(),
+            sections: release
+                .sections
+                .iter()
+                .map(|section| SectionOutput {
+                    name: section.name.clone(),
+                    items: section.items(),
+                })
+                .collect(),
+            body: release.body(),
+        }
+    }
+}
+
+/// Find the position of a version in the CHANGELOG. `latest` is the latest
+/// release, and `unreleased` the changes that are not released yet
+fn find_release(
+    config: &Config,
+    changelog: &Changelog,
+    version: &str,
+) -> Result<usize, VersionWiseError> {
+    let index = match version.to_lowercase().as_str() {
+        "latest" => changelog.latest_index(),
+        "unreleased" => changelog.unreleased_index(),
+        _ => changelog.release_index(version),
+    };
+    index.ok_or_else(|| {
+        VersionWiseError::Version(format!(
+            "Couldn't find the version `{}` in the `{}`.",
+            version, config.changelog
+        ))
+    })
+}
+
+/// Releases selected by a version or a range, as positions in the CHANGELOG.
+///
+/// A range `FROM..TO` works like in git: it has the releases after `FROM`, up
+/// to `TO` included. Without `FROM`, it starts at the first release, and
+/// without `TO` it ends at the latest one.
+fn select_releases(
+    config: &Config,
+    changelog: &Changelog,
+    selection: &str,
+) -> Result<Range<usize>, VersionWiseError> {
+    let Some((from, to)) = selection.split_once("..") else {
+        let index = find_release(config, changelog, selection)?;
+        return Ok(index..index + 1);
+    };
+    // The releases go from the newest one, so `TO` comes first
+    let start = match to.trim() {
+        "" => find_release(config, changelog, "latest")?,
+        to => find_release(config, changelog, to)?,
+    };
+    let end = match from.trim() {
+        "" => changelog.releases.len(),
+        from => find_release(config, changelog, from)?,
+    };
+    if start > end {
+        return Err(VersionWiseError::Input(format!(
+            "The range `{}` is reversed. Write the oldest version first, like `0.2.0..0.4.0`.",
+            selection
+        )));
+    }
+    Ok(start..end)
+}
+
+/// Regex of a markdown link, inline (`[text](url)`) or reference-style (`[text]`)
+fn link_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\[([^\]]*)\](\([^)]*\))?").unwrap())
+}
+
+/// Remove the markdown syntax of a line, for the plain text output
+fn plain_text(line: &str) -> String {
+    let line = line.trim_start_matches('#').trim_start();
+    link_regex()
+        .replace_all(line, "$1")
+        .replace("**", "")
+        .replace('`', "")
+}
+
+/// Write the selected releases in the requested format
+fn render_notes(
+    releases: &[Release],
+    format: &str,
+    with_heading: bool,
+) -> Result<String, VersionWiseError> {
+    match format {
+        "json" => {
+            let output: Vec<ReleaseOutput> = releases.iter().map(ReleaseOutput::new).collect();
+            serde_json::to_string_pretty(&output)
+                .map_err(|e| VersionWiseError::parse("<json output>", e.to_string()))
+        }
+        _ => {
+            let notes: Vec<String> = releases
+                .iter()
+                .map(|release| match (with_heading, release.body()) {
+                    (true, body) if body.is_empty() => release.heading.clone(),
+                    (true, body) => format!("{}\n\n{}", release.heading, body),
+                    (false, body) => body,
+                })
+                .collect();
+            let notes = notes.join("\n\n");
+            match format {
+                "text" => Ok(notes
+                    .lines()
+                    .map(plain_text)
+                    .collect::<Vec<String>>()
+                    .join("\n")),
+                _ => Ok(notes),
+            }
+        }
+    }
+}
+
+pub fn release_notes(matches: &ArgMatches, config: &Config) -> Result<(), VersionWiseError> {
+    let changelog = open_changelog(config)?;
+    // Find the releases to print
+    let selection = matches.get_one::<String>("version").unwrap();
+    let releases = &changelog.releases[select_releases(config, &changelog, selection)?];
+    // A single release is printed without its heading, unless it's requested
+    let with_heading = releases.len() > 1 || matches.get_flag("heading");
+    let format = matches.get_one::<String>("format").unwrap();
+    println!("{}", render_notes(releases, format, with_heading)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANGELOG: &str = "# Changelog\n\n## [Unreleased]\n\n- Pending.\n\n## [0.4.0]\n\n- Four.\n\n## [0.3.0]\n\n- Three.\n\n## [0.2.0]\n\n- Two.\n\n## [0.1.0]\n\n- One.\n";
+
+    /// Versions of the releases selected
+    fn select(selection: &str) -> Result<Vec<String>, VersionWiseError> {
+        let config = Config::from_toml("version_path = [\"pyproject.toml\"]");
+        let changelog = Changelog::parse(CHANGELOG);
+        let range = select_releases(&config, &changelog, selection)?;
+        Ok(changelog.releases[range]
+            .iter()
+            .map(|release| release.version.clone())
+            .collect())
+    }
+
+    #[test]
+    fn selects_a_single_release() {
+        assert_eq!(select("0.3.0").unwrap(), ["0.3.0"]);
+        assert_eq!(select("v0.3.0").unwrap(), ["0.3.0"]);
+        assert_eq!(select("latest").unwrap(), ["0.4.0"]);
+        assert_eq!(select("unreleased").unwrap(), ["Unreleased"]);
+    }
+
+    #[test]
+    fn ranges_exclude_the_start_and_include_the_end() {
+        assert_eq!(select("0.2.0..0.4.0").unwrap(), ["0.4.0", "0.3.0"]);
+        assert_eq!(select("0.3.0..0.3.0").unwrap(), Vec::<String>::new());
+        assert_eq!(
+            select("0.1.0..unreleased").unwrap(),
+            ["Unreleased", "0.4.0", "0.3.0", "0.2.0"]
+        );
+    }
+
+    #[test]
+    fn ranges_without_an_end_are_open() {
+        // Without `TO`, up to the latest release, and not the unreleased changes
+        assert_eq!(select("0.2.0..").unwrap(), ["0.4.0", "0.3.0"]);
+        // Without `FROM`, since the first release
+        assert_eq!(select("..0.3.0").unwrap(), ["0.3.0", "0.2.0", "0.1.0"]);
+        assert_eq!(select("..").unwrap(), ["0.4.0", "0.3.0", "0.2.0", "0.1.0"]);
+    }
+
+    #[test]
+    fn rejects_reversed_ranges_and_unknown_versions() {
+        let error = select("0.4.0..0.2.0").unwrap_err();
+        assert!(matches!(error, VersionWiseError::Input(_)), "{}", error);
+        assert!(error.to_string().contains("`0.4.0..0.2.0` is reversed"));
+        for selection in ["0.9.0", "0.9.0..0.4.0", "0.2.0..0.9.0"] {
+            let error = select(selection).unwrap_err();
+            assert!(matches!(error, VersionWiseError::Version(_)), "{}", error);
+            assert!(error.to_string().contains("`0.9.0`"), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+/// ================================ ///
+///          OPTIONS :: Pre          ///
+/// ================================ ///
+/// Enter and exit the pre-release mode. While it is active, `bump` releases
+/// `X.Y.Z-<tag>.N` versions and keeps the changesets for the final release.
+use clap::ArgMatches;
+use colored::*;
+// Local imports
+use crate::utilities::{
+    create_changeset_folder, find_version, read_pre_state, remove_pre_state, write_pre_state,
+    Config, PreMode, PreState, VersionWiseError,
+};
+
+/// Validate the pre-release tag. It should be a valid SemVer identifier
+/// that is not only a number, like `alpha`, `beta` or `rc`.
+fn validate_tag(tag: &str) -> Result<(), VersionWiseError> {
+    let valid_chars = tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    let only_digits = tag.chars().all(|c| c.is_ascii_digit());
+    if tag.is_empty() || !valid_chars || only_digits {
+        return Err(VersionWiseError::Input(format!(
+            "Invalid pre-release tag `{}`. Use letters, digits and hyphens, like `alpha` or `rc`.",
+            tag
+        )));
+    }
+    Ok(())
+}
+
+fn enter_pre_release(config: &Config, tag: &str) -> Result<(), VersionWiseError> {
+    validate_tag(tag)?;
+    // Only one pre-release cycle at a time
+    if let Some(state) = read_pre_state(config)? {
+        return Err(VersionWiseError::Input(format!(
+            "The project is already in the pre-release mode `{}`. Use `versionwise pre exit` first.",
+            state.tag
+        )));
+    }
+    // Check that the version scheme supports this tag
+    let base_version = find_version(config)?;
+    config.version_scheme.pre_release(&base_version, tag, 0)?;
+    // Store the state
+    create_changeset_folder(config)?;
+    write_pre_state(
+        config,
+        &PreState {
+            mode: PreMode::Pre,
+            tag: tag.to_string(),
+            base_version,
+            counter: 0,
+            consumed: Vec::new(),
+        },
+    )?;
+    println!(
+        "Entered the pre-release mode `{}`. Every `versionwise bump` will release a new `{}` pre-release.",
+        tag.green(),
+        tag
+    );
+    Ok(())
+}
+
+fn exit_pre_release(config: &Config) -> Result<(), VersionWiseError> {
+    let Some(mut state) = read_pre_state(config)? else {
+        return Err(VersionWiseError::Input(
+            "The project is not in pre-release mode. Use `versionwise pre enter <tag>` first."
+                .to_string(),
+        ));
+    };
+    // Without pre-releases there's nothing to collapse in a final release,
+    // so the pending changesets are left for a regular one
+    if state.consumed.is_empty() {
+        remove_pre_state(config)?;
+        println!(
+            "Left the pre-release mode `{}`. No pre-release was done, so the next `versionwise bump` is a regular release.",
+            state.tag.green()
+        );
+        return Ok(());
+    }
+    // The final release is done by the next bump
+    state.mode = PreMode::Exit;
+    write_pre_state(config, &state)?;
+    println!(
+        "Exiting the pre-release mode `{}`. Run `versionwise bump` to release the final version with every changeset of the cycle.",
+        state.tag.green()
+    );
+    Ok(())
+}
+
+pub fn pre_release(matches: &ArgMatches, config: &Config) -> Result<(), VersionWiseError> {
+    match matches.subcommand() {
+        Some(("enter", sub_matches)) => {
+            enter_pre_release(config, sub_matches.get_one::<String>("tag").unwrap())
+        }
+        Some(("exit", _)) => exit_pre_release(config),
+        _ => unreachable!("The `pre` command requires a subcommand"),
+    }
+}
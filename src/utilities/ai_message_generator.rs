@@ -8,6 +8,8 @@ use std::str;
 
 // Import the AI modules
 use crate::utilities::ai_calls::{gemini, openai};
+use crate::utilities::config::AiSettings;
+use crate::utilities::VersionWiseError;
 
 /// Configuration for the AI message generator
 #[derive(Debug, Clone)]
@@ -21,43 +23,57 @@ pub struct AIConfig {
 }
 
 impl AIConfig {
-    /// Builds a new AIConfig from environment variables
+    /// Builds a new AIConfig from the `[ai]` settings of the configuration.
+    /// The missing settings are read from environment variables
     ///
     /// # Returns
     ///
     /// A new AIConfig instance
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if any of the required environment variables are not set:
-    /// - AI_PROVIDER
-    /// - API_KEY
-    /// - MODEL
-    pub fn build() -> Self {
+    /// Returns a `VersionWiseError::Ai` if a setting is neither configured nor
+    /// set in its environment variable, or the provider is not supported:
+    /// - AI_PROVIDER (`ai.provider`)
+    /// - API_KEY (or the variable named by `ai.api_key_env`)
+    /// - MODEL (`ai.model`)
+    pub fn build(settings: &AiSettings) -> Result<Self, VersionWiseError> {
         // Load environment variables from .env file
         dotenv().ok();
+        // Get a variable from the environment, or say which one is missing
+        let var = |name: &str| {
+            env::var(name)
+                .map_err(|_| VersionWiseError::Ai(format!("{} must be set in .env file", name)))
+        };
 
         // Get provider from env
-        let provider = env::var("AI_PROVIDER")
-            .expect("AI_PROVIDER must be set in .env file")
-            .to_lowercase();
+        let provider = match &settings.provider {
+            Some(provider) => provider.clone(),
+            None => var("AI_PROVIDER")?,
+        }
+        .to_lowercase();
 
         // Validate provider
         if provider != "openai" && provider != "gemini" {
-            panic!("AI_PROVIDER must be either 'openai' or 'gemini'");
+            return Err(VersionWiseError::Ai(
+                "AI_PROVIDER must be either 'openai' or 'gemini'".to_string(),
+            ));
         }
 
         // Get API key from env
-        let api_key = env::var("API_KEY").expect("API_KEY must be set in .env file");
+        let api_key = var(settings.api_key_env.as_deref().unwrap_or("API_KEY"))?;
 
         // Get model from env
-        let model = env::var("MODEL").expect("MODEL must be set in .env file");
+        let model = match &settings.model {
+            Some(model) => model.clone(),
+            None => var("MODEL")?,
+        };
 
-        Self {
+        Ok(Self {
             api_key,
             model,
             provider,
-        }
+        })
     }
 }
 
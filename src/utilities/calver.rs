@@ -0,0 +1,387 @@
+/// ================================ ///
+///       UTILITIES :: CalVer        ///
+/// ================================ ///
+/// Calendar versioning (https://calver.org). The versions are described by a
+/// format like `YYYY.MM.MICRO`, and the next one is derived from the current
+/// date and the previous version.
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+// Local imports
+use crate::utilities::date::Date;
+use crate::utilities::version::Identifier;
+use crate::utilities::VersionWiseError;
+
+/// Format used when `calver_format` is not configured
+pub const DEFAULT_CALVER_FORMAT: &str = "YYYY.MM.MICRO";
+
+/// A part of the CalVer format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    /// Full year (`2006`, `2016`)
+    FullYear,
+    /// Short year (`6`, `16`, `106`)
+    ShortYear,
+    /// Zero-padded year (`06`, `16`, `106`)
+    ZeroPaddedYear,
+    /// Short month (`1`, `2`, ..., `12`)
+    Month,
+    /// Zero-padded month (`01`, `02`, ..., `12`)
+    ZeroPaddedMonth,
+    /// Short day (`1`, `2`, ..., `31`)
+    Day,
+    /// Zero-padded day (`01`, `02`, ..., `31`)
+    ZeroPaddedDay,
+    /// Number of the release inside of the period
+    Micro,
+}
+
+impl Token {
+    fn from_name(name: &str) -> Option<Token> {
+        match name {
+            "YYYY" => Some(Token::FullYear),
+            "YY" => Some(Token::ShortYear),
+            "0Y" => Some(Token::ZeroPaddedYear),
+            "MM" => Some(Token::Month),
+            "0M" => Some(Token::ZeroPaddedMonth),
+            "DD" => Some(Token::Day),
+            "0D" => Some(Token::ZeroPaddedDay),
+            "MICRO" => Some(Token::Micro),
+            _ => None,
+        }
+    }
+
+    /// Value of the token for the given date. `None` for the MICRO
+    fn value(&self, date: &Date) -> Option<u64> {
+        match self {
+            Token::FullYear => Some(date.year),
+            Token::ShortYear | Token::ZeroPaddedYear => Some(date.year.saturating_sub(2000)),
+            Token::Month | Token::ZeroPaddedMonth => Some(date.month),
+            Token::Day | Token::ZeroPaddedDay => Some(date.day),
+            Token::Micro => None,
+        }
+    }
+
+    /// Range of the values accepted for the token
+    fn accepts(&self, value: u64) -> bool {
+        match self {
+            Token::Month | Token::ZeroPaddedMonth => (1..=12).contains(&value),
+            Token::Day | Token::ZeroPaddedDay => (1..=31).contains(&value),
+            _ => true,
+        }
+    }
+
+    fn is_zero_padded(&self) -> bool {
+        matches!(
+            self,
+            Token::ZeroPaddedYear | Token::ZeroPaddedMonth | Token::ZeroPaddedDay
+        )
+    }
+}
+
+/// A CalVer format, like `YYYY.MM.MICRO` or `0Y.0M.0D`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalVerFormat {
+    tokens: Vec<Token>,
+}
+
+impl FromStr for CalVerFormat {
+    type Err = VersionWiseError;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            VersionWiseError::Config(format!(
+                "Invalid `calver_format` `{}`: {}. Use dot-separated YYYY, YY, 0Y, MM, 0M, DD, 0D and MICRO, like `YYYY.MM.MICRO`.",
+                format, reason
+            ))
+        };
+        let tokens = format
+            .split('.')
+            .map(|name| {
+                Token::from_name(name.trim()).ok_or_else(|| invalid(&format!("unknown `{}`", name)))
+            })
+            .collect::<Result<Vec<Token>, VersionWiseError>>()?;
+        // The MICRO, if it's used, is the last part of the version
+        let micros = tokens.iter().filter(|t| **t == Token::Micro).count();
+        if micros > 1 || (micros == 1 && tokens.last() != Some(&Token::Micro)) {
+            return Err(invalid("MICRO can only be the last part"));
+        }
+        if micros == tokens.len() {
+            return Err(invalid("it needs at least one date part"));
+        }
+        Ok(CalVerFormat { tokens })
+    }
+}
+
+impl fmt::Display for CalVerFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self
+            .tokens
+            .iter()
+            .map(|token| match token {
+                Token::FullYear => "YYYY",
+                Token::ShortYear => "YY",
+                Token::ZeroPaddedYear => "0Y",
+                Token::Month => "MM",
+                Token::ZeroPaddedMonth => "0M",
+                Token::Day => "DD",
+                Token::ZeroPaddedDay => "0D",
+                Token::Micro => "MICRO",
+            })
+            .collect();
+        write!(f, "{}", names.join("."))
+    }
+}
+
+/// A version parsed with a CalVer format. Pre-releases use the SemVer
+/// notation (`2024.5.0-rc.1`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CalVerVersion {
+    parts: Vec<u64>,
+    pre: Vec<Identifier>,
+}
+
+impl CalVerVersion {
+    /// The parts that come from the date, without the MICRO
+    fn period(&self, format: &CalVerFormat) -> Vec<u64> {
+        self.parts
+            .iter()
+            .zip(&format.tokens)
+            .filter(|(_, token)| **token != Token::Micro)
+            .map(|(value, _)| *value)
+            .collect()
+    }
+
+    fn micro(&self, format: &CalVerFormat) -> Option<u64> {
+        match format.tokens.last() {
+            Some(Token::Micro) => self.parts.last().copied(),
+            _ => None,
+        }
+    }
+}
+
+impl Ord for CalVerVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.parts.cmp(&other.parts).then_with(|| {
+            match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                // A pre-release has lower precedence than the release
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            }
+        })
+    }
+}
+
+impl PartialOrd for CalVerVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl CalVerFormat {
+    fn invalid_version(&self, version: &str, reason: &str) -> VersionWiseError {
+        VersionWiseError::Version(format!(
+            "`{}` is not a valid `{}` calendar version: {}",
+            version, self, reason
+        ))
+    }
+
+    fn parse(&self, version: &str) -> Result<CalVerVersion, VersionWiseError> {
+        let text = version.trim();
+        let text = text.strip_prefix('v').unwrap_or(text);
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (text, None),
+        };
+        // One number for each part of the format
+        let numbers: Vec<&str> = core.split('.').collect();
+        if numbers.len() != self.tokens.len() {
+            return Err(self.invalid_version(
+                version,
+                &format!("it should have {} parts", self.tokens.len()),
+            ));
+        }
+        let mut parts = Vec::new();
+        for (number, token) in numbers.iter().zip(&self.tokens) {
+            let value: u64 = number
+                .parse()
+                .map_err(|_| self.invalid_version(version, "every part should be a number"))?;
+            if !token.accepts(value) {
+                return Err(self.invalid_version(version, &format!("`{}` is out of range", number)));
+            }
+            parts.push(value);
+        }
+        // The pre-release identifiers
+        let pre = match pre {
+            None => Vec::new(),
+            Some(pre) => pre
+                .split('.')
+                .map(|identifier| {
+                    if identifier.is_empty()
+                        || !identifier
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+                    {
+                        return Err(self.invalid_version(
+                            version,
+                            &format!("invalid pre-release identifier `{}`", identifier),
+                        ));
+                    }
+                    Ok(match identifier.parse() {
+                        Ok(number) => Identifier::Numeric(number),
+                        Err(_) => Identifier::AlphaNumeric(identifier.to_string()),
+                    })
+                })
+                .collect::<Result<Vec<Identifier>, VersionWiseError>>()?,
+        };
+        Ok(CalVerVersion { parts, pre })
+    }
+
+    fn render(&self, version: &CalVerVersion) -> String {
+        let parts: Vec<String> = version
+            .parts
+            .iter()
+            .zip(&self.tokens)
+            .map(|(value, token)| match token.is_zero_padded() {
+                true => format!("{:02}", value),
+                false => value.to_string(),
+            })
+            .collect();
+        let mut text = parts.join(".");
+        if !version.pre.is_empty() {
+            let pre: Vec<String> = version.pre.iter().map(|i| i.to_string()).collect();
+            text.push_str(&format!("-{}", pre.join(".")));
+        }
+        text
+    }
+
+    /// Validate a version, returning it in this format
+    pub fn normalize(&self, version: &str) -> Result<String, VersionWiseError> {
+        Ok(self.render(&self.parse(version)?))
+    }
+
+    /// Compare two versions. The parts are compared in the order of the format
+    pub fn compare(&self, a: &str, b: &str) -> Result<Ordering, VersionWiseError> {
+        Ok(self.parse(a)?.cmp(&self.parse(b)?))
+    }
+
+    /// Calculate the version released at `date` after `version`.
+    ///
+    /// A release in a new period starts the MICRO at zero, and one in the same
+    /// period as `version` increments it. A pre-release is released as its own
+    /// version when it's in the current period.
+    pub fn bump_at(&self, version: &str, date: &Date) -> Result<String, VersionWiseError> {
+        let previous = self.parse(version)?;
+        let period: Vec<u64> = self.tokens.iter().filter_map(|t| t.value(date)).collect();
+        let micro = match previous.period(self).cmp(&period) {
+            Ordering::Less => Some(0),
+            Ordering::Equal if !previous.pre.is_empty() => previous.micro(self),
+            Ordering::Equal => match previous.micro(self) {
+                Some(micro) => Some(micro + 1),
+                None => {
+                    return Err(VersionWiseError::Version(format!(
+                        "The version `{}` was already released on {}. Add MICRO to the `calver_format` to release more than once per period.",
+                        version, date
+                    )))
+                }
+            },
+            Ordering::Greater => {
+                return Err(VersionWiseError::Version(format!(
+                    "The version `{}` is newer than the date {}.",
+                    version, date
+                )))
+            }
+        };
+        let mut parts = period;
+        // Only formats with MICRO have a value for it
+        if self.tokens.last() == Some(&Token::Micro) {
+            parts.extend(micro);
+        }
+        Ok(self.render(&CalVerVersion {
+            parts,
+            pre: Vec::new(),
+        }))
+    }
+
+    /// Build the `number`-th pre-release of a release version (`2024.5.0-rc.0`)
+    pub fn pre_release(
+        &self,
+        release: &str,
+        tag: &str,
+        number: u64,
+    ) -> Result<String, VersionWiseError> {
+        let mut version = self.parse(release)?;
+        version.pre = vec![
+            Identifier::AlphaNumeric(tag.to_string()),
+            Identifier::Numeric(number),
+        ];
+        Ok(self.render(&version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(text: &str) -> CalVerFormat {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn bumps_the_micro_in_the_same_period() {
+        let may = Date::new(2024, 5, 17);
+        assert_eq!(
+            format("YYYY.MM.MICRO").bump_at("2024.5.0", &may).unwrap(),
+            "2024.5.1"
+        );
+        assert_eq!(
+            format("0Y.0M.MICRO").bump_at("24.05.3", &may).unwrap(),
+            "24.05.4"
+        );
+        // A pre-release of the period is released as its own version
+        assert_eq!(
+            format("YYYY.MM.MICRO")
+                .bump_at("2024.5.2-rc.1", &may)
+                .unwrap(),
+            "2024.5.2"
+        );
+        // Without MICRO there can only be one release per period
+        assert!(format("YYYY.0M.0D").bump_at("2024.05.17", &may).is_err());
+    }
+
+    #[test]
+    fn starts_a_new_period() {
+        let june = Date::new(2024, 6, 1);
+        assert_eq!(
+            format("YYYY.MM.MICRO").bump_at("2024.5.7", &june).unwrap(),
+            "2024.6.0"
+        );
+        assert_eq!(
+            format("YYYY.0M.0D").bump_at("2024.05.17", &june).unwrap(),
+            "2024.06.01"
+        );
+        assert_eq!(format("YY.MM").bump_at("23.12", &june).unwrap(), "24.6");
+        // A version newer than the date can't be bumped
+        assert!(format("YYYY.MM.MICRO").bump_at("2024.7.0", &june).is_err());
+    }
+
+    #[test]
+    fn validates_the_format_and_the_versions() {
+        for invalid in ["YYYY.MICRO.MM", "MICRO", "YYYY.WW"] {
+            assert!(invalid.parse::<CalVerFormat>().is_err(), "{}", invalid);
+        }
+        assert_eq!(
+            format("YYYY.0M.MICRO").normalize("2024.5.1").unwrap(),
+            "2024.05.1"
+        );
+        assert!(format("YYYY.MM.MICRO").normalize("2024.13.0").is_err());
+        assert_eq!(
+            format("YYYY.MM.MICRO")
+                .compare("2024.5.0-rc.1", "2024.5.0")
+                .unwrap(),
+            Ordering::Less
+        );
+    }
+}
@@ -0,0 +1,434 @@
+/// ================================ ///
+///      UTILITIES :: Changelog      ///
+/// ================================ ///
+/// Structured model of a CHANGELOG file. It's parsed from the markdown and
+/// rendered back without losing anything, so the releases can be edited
+/// instead of splicing lines:
+///
+/// ```markdown
+/// # Changelog                          <- preamble
+///
+/// ## [1.1.0] - 2024-05-01              <- release
+///
+/// ### Added                            <- section
+///
+/// - New feature.
+///
+/// [1.1.0]: https://host/compare/...    <- footer
+/// ```
+use regex::Regex;
+use std::sync::OnceLock;
+// Local imports
+use crate::utilities::VersionWiseError;
+
+/// Regex of an ISO 8601 date (`2024-05-01`)
+fn date_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\b\d{4}-\d{2}-\d{2}\b").unwrap())
+}
+
+/// Whether the line is a reference-style link definition (`[label]: url`)
+pub fn is_link_definition(line: &str) -> bool {
+    line.starts_with('[') && line.contains("]: ")
+}
+
+/// A `###` section of a release, like `### Added`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// Heading line, as it's written
+    pub heading: String,
+    /// Name of the section (`Added`)
+    pub name: String,
+    /// Lines of the section, after the heading
+    pub lines: Vec<String>,
+}
+
+impl Section {
+    fn new(heading: &str) -> Section {
+        Section {
+            heading: heading.to_string(),
+            name: heading.trim_start_matches('#').trim().to_string(),
+            lines: Vec::new(),
+        }
+    }
+
+    /// Bullet items of the section, without the `- `. The lines that continue
+    /// an item are joined to it
+    pub fn items(&self) -> Vec<String> {
+        let mut items: Vec<String> = Vec::new();
+        for line in &self.lines {
+            let trimmed = line.trim();
+            match trimmed.strip_prefix("- ").or(trimmed.strip_prefix("* ")) {
+                Some(item) if !line.starts_with(' ') => items.push(item.to_string()),
+                _ if trimmed.is_empty() => {}
+                _ => match items.last_mut() {
+                    Some(item) => {
+                        item.push(' ');
+                        item.push_str(trimmed);
+                    }
+                    None => items.push(trimmed.to_string()),
+                },
+            }
+        }
+        items
+    }
+}
+
+/// A `##` release of the CHANGELOG, like `## [1.1.0] - 2024-05-01`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    /// Heading line, as it's written
+    pub heading: String,
+    /// Version of the release, or `Unreleased`
+    pub version: String,
+    /// Date of the release, if the heading has one
+    pub date: Option<String>,
+    /// Lines between the heading and the first section
+    pub lines: Vec<String>,
+    pub sections: Vec<Section>,
+}
+
+impl Release {
+    fn new(heading: &str) -> Release {
+        let title = heading.trim_start_matches('#').trim();
+        // `[1.1.0] - 2024-05-01`, `1.1.0 (2024-05-01)` or `1.1.0`
+        let version = match title.strip_prefix('[') {
+            Some(rest) => rest.split(']').next().unwrap_or_default(),
+            None => title.split_whitespace().next().unwrap_or_default(),
+        };
+        Release {
+            heading: heading.to_string(),
+            version: version.to_string(),
+            date: date_regex()
+                .find(title)
+                .map(|date| date.as_str().to_string()),
+            lines: Vec::new(),
+            sections: Vec::new(),
+        }
+    }
+
+    /// Parse the entry of a single release, as it's written by `bump`
+    pub fn parse_entry(entry: &str) -> Result<Release, VersionWiseError> {
+        Changelog::parse(entry)
+            .releases
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                VersionWiseError::Config(format!(
+                    "The CHANGELOG entry should start with a `## ` heading. Check the `changelog_template`, that renders:\n{}",
+                    entry
+                ))
+            })
+    }
+
+    pub fn is_unreleased(&self) -> bool {
+        self.version.eq_ignore_ascii_case("unreleased")
+    }
+
+    /// Label of the reference-style link of the heading, like `1.1.0` for `## [1.1.0]`
+    pub fn link_label(&self) -> Option<&str> {
+        self.heading
+            .trim_start_matches('#')
+            .trim()
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once(']'))
+            .map(|(label, _)| label)
+    }
+
+    /// Lines at the end of the release, where the text that follows the heading goes
+    fn last_line_mut(&mut self) -> &mut Vec<String> {
+        match self.sections.last_mut() {
+            Some(section) => &mut section.lines,
+            None => &mut self.lines,
+        }
+    }
+
+    /// End the release with a single blank line, so it's separated from the next one
+    fn end_with_blank_line(&mut self) {
+        let lines = self.last_line_mut();
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+        lines.push(String::new());
+    }
+
+    /// Add the sections of another release of the same version. The items of a
+    /// section that already exists go after its items, and the new sections
+    /// go at the end of the release
+    pub fn merge(&mut self, other: Release) {
+        // The blank lines that separate the release from the next one stay at the end
+        let mut trailing = Vec::new();
+        let last = self.last_line_mut();
+        while last.last().is_some_and(|line| line.trim().is_empty()) {
+            trailing.push(last.pop().unwrap());
+        }
+        for section in other.sections {
+            // Only the content of the section, without the blank lines around it
+            let start = section.lines.iter().position(|l| !l.trim().is_empty());
+            let end = section.lines.iter().rposition(|l| !l.trim().is_empty());
+            let content = match (start, end) {
+                (Some(start), Some(end)) => section.lines[start..=end].to_vec(),
+                _ => Vec::new(),
+            };
+            match self.sections.iter_mut().find(|s| s.name == section.name) {
+                Some(existing) => {
+                    let end = existing
+                        .lines
+                        .iter()
+                        .rposition(|line| !line.trim().is_empty())
+                        .map_or(existing.lines.len(), |index| index + 1);
+                    existing.lines.splice(end..end, content);
+                }
+                None => {
+                    let last = self.last_line_mut();
+                    if last.last().is_some_and(|line| !line.trim().is_empty()) {
+                        last.push(String::new());
+                    }
+                    let mut lines = vec![String::new()];
+                    lines.extend(content);
+                    self.sections.push(Section { lines, ..section });
+                }
+            }
+        }
+        self.last_line_mut().extend(trailing);
+    }
+
+    fn render(&self, lines: &mut Vec<String>) {
+        lines.push(self.heading.clone());
+        lines.extend(self.lines.iter().cloned());
+        for section in &self.sections {
+            lines.push(section.heading.clone());
+            lines.extend(section.lines.iter().cloned());
+        }
+    }
+
+    /// Markdown of the release without its heading, and without the blank
+    /// lines around it
+    pub fn body(&self) -> String {
+        let mut lines = Vec::new();
+        self.render(&mut lines);
+        lines.remove(0);
+        lines.join("\n").trim_matches('\n').to_string()
+    }
+}
+
+/// A CHANGELOG file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Changelog {
+    /// Lines before the first release (title, description, ...)
+    pub preamble: Vec<String>,
+    /// Releases, from the newest one
+    pub releases: Vec<Release>,
+    /// Link definitions at the end of the file
+    pub footer: Vec<String>,
+    /// Whether the file ends with a newline
+    trailing_newline: bool,
+}
+
+impl Changelog {
+    /// Parse the markdown of a CHANGELOG
+    pub fn parse(content: &str) -> Changelog {
+        let trailing_newline = content.ends_with('\n');
+        let mut lines: Vec<&str> = content.split('\n').collect();
+        if trailing_newline {
+            lines.pop();
+        }
+        // The footer is the block of link definitions at the end
+        let mut footer_start = lines.len();
+        for (index, line) in lines.iter().enumerate().rev() {
+            if is_link_definition(line) {
+                footer_start = index;
+            } else if !line.trim().is_empty() {
+                break;
+            }
+        }
+        let footer = lines[footer_start..]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        // Split the rest in releases and sections. Code blocks can't have headings
+        let mut changelog = Changelog {
+            preamble: Vec::new(),
+            releases: Vec::new(),
+            footer,
+            trailing_newline,
+        };
+        let mut in_code_block = false;
+        for line in &lines[..footer_start] {
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+            }
+            let is_heading = |level: &str| !in_code_block && line.starts_with(level);
+            match changelog.releases.last_mut() {
+                _ if is_heading("## ") => changelog.releases.push(Release::new(line)),
+                None => changelog.preamble.push(line.to_string()),
+                Some(release) if is_heading("### ") => release.sections.push(Section::new(line)),
+                Some(release) => release.last_line_mut().push(line.to_string()),
+            }
+        }
+        changelog
+    }
+
+    /// Render the CHANGELOG back to markdown
+    pub fn render(&self) -> String {
+        let mut lines = self.preamble.clone();
+        for release in &self.releases {
+            release.render(&mut lines);
+        }
+        lines.extend(self.footer.iter().cloned());
+        let mut content = lines.join("\n");
+        if self.trailing_newline {
+            content.push('\n');
+        }
+        content
+    }
+
+    /// Position of the `Unreleased` release, if there's one
+    pub fn unreleased_index(&self) -> Option<usize> {
+        self.releases
+            .iter()
+            .position(|release| release.is_unreleased())
+    }
+
+    /// Position of a release by its version. The `v` prefix is optional
+    pub fn release_index(&self, version: &str) -> Option<usize> {
+        let without_prefix =
+            |version: &str| version.strip_prefix('v').unwrap_or(version).to_string();
+        self.releases.iter().position(|release| {
+            without_prefix(&release.version).eq_ignore_ascii_case(&without_prefix(version))
+        })
+    }
+
+    /// Position of the latest release, skipping the `Unreleased` one
+    pub fn latest_index(&self) -> Option<usize> {
+        self.releases
+            .iter()
+            .position(|release| !release.is_unreleased())
+    }
+
+    /// Add a release before the previous ones. The `Unreleased` release stays
+    /// on top, unless `replace_unreleased` is set and it's replaced by the new one.
+    /// Returns the position of the new release
+    pub fn insert_release(&mut self, release: Release, replace_unreleased: bool) -> usize {
+        let index = match self.unreleased_index() {
+            Some(index) if replace_unreleased => {
+                self.releases.remove(index);
+                index
+            }
+            Some(index) => index + 1,
+            None => 0,
+        };
+        self.insert_release_at(index, release);
+        index
+    }
+
+    /// Write the `Unreleased` release, replacing the previous one
+    pub fn set_unreleased(&mut self, release: Release) {
+        let index = match self.unreleased_index() {
+            Some(index) => {
+                self.releases.remove(index);
+                index
+            }
+            None => 0,
+        };
+        self.insert_release_at(index, release);
+    }
+
+    /// Add a release at the given position, keeping a blank line around it
+    pub fn insert_release_at(&mut self, index: usize, mut release: Release) {
+        let previous = match index {
+            0 => &mut self.preamble,
+            _ => self.releases[index - 1].last_line_mut(),
+        };
+        if previous.last().is_some_and(|line| !line.trim().is_empty()) {
+            previous.push(String::new());
+        }
+        if index < self.releases.len() || !self.footer.is_empty() {
+            release.end_with_blank_line();
+        }
+        // The entries end with a newline, even if the file didn't
+        self.trailing_newline = true;
+        self.releases.insert(index, release);
+    }
+
+    /// End the text before the footer with a single blank line
+    pub fn end_with_blank_line(&mut self) {
+        match self.releases.last_mut() {
+            Some(release) => release.end_with_blank_line(),
+            None => {
+                while self
+                    .preamble
+                    .last()
+                    .is_some_and(|line| line.trim().is_empty())
+                {
+                    self.preamble.pop();
+                }
+                self.preamble.push(String::new());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANGELOG: &str = "# Changelog\n\n## [Unreleased]\n\n### Added\n\n- New feature.\n  More details.\n\n## [1.0.0] - 2024-05-01\n\nFirst release.\n\n```markdown\n## Not a release\n```\n\n### Fixed\n\n- A bug.\n\n[unreleased]: https://host/compare/v1.0.0...HEAD\n[1.0.0]: https://host/releases/tag/v1.0.0\n";
+
+    #[test]
+    fn renders_the_repository_changelog_losslessly() {
+        let content = include_str!("../../CHANGELOG.md");
+        assert_eq!(Changelog::parse(content).render(), content);
+    }
+
+    #[test]
+    fn renders_back_without_trailing_newline() {
+        for content in [CHANGELOG, CHANGELOG.trim_end(), "", "# Changelog"] {
+            assert_eq!(Changelog::parse(content).render(), content);
+        }
+    }
+
+    #[test]
+    fn parses_releases_sections_and_footer() {
+        let changelog = Changelog::parse(CHANGELOG);
+        assert_eq!(changelog.preamble, vec!["# Changelog", ""]);
+        assert_eq!(changelog.unreleased_index(), Some(0));
+        assert_eq!(changelog.latest_index(), Some(1));
+        assert_eq!(changelog.release_index("v1.0.0"), Some(1));
+        // The heading inside of the code block is part of the release
+        let release = &changelog.releases[1];
+        assert_eq!(release.date.as_deref(), Some("2024-05-01"));
+        assert_eq!(release.link_label(), Some("1.0.0"));
+        assert_eq!(release.sections.len(), 1);
+        assert_eq!(release.sections[0].items(), vec!["A bug."]);
+        assert_eq!(
+            changelog.releases[0].sections[0].items(),
+            vec!["New feature. More details."]
+        );
+        assert_eq!(changelog.footer.len(), 2);
+    }
+
+    #[test]
+    fn inserts_a_release_after_unreleased() {
+        let mut changelog = Changelog::parse(CHANGELOG);
+        let release =
+            Release::parse_entry("## [1.1.0] - 2024-06-01\n\n### Added\n\n- More.\n").unwrap();
+        assert_eq!(changelog.insert_release(release, false), 1);
+        let rendered = changelog.render();
+        assert!(rendered.contains(
+            "  More details.\n\n## [1.1.0] - 2024-06-01\n\n### Added\n\n- More.\n\n## [1.0.0]"
+        ));
+        assert_eq!(Changelog::parse(&rendered), changelog);
+    }
+
+    #[test]
+    fn merges_a_release_of_the_same_version() {
+        let mut changelog = Changelog::parse(CHANGELOG);
+        let entry =
+            "## [1.0.0] - 2024-06-01\n\n### Fixed\n\n- Another bug.\n\n### Changed\n\n- CI.\n";
+        changelog.releases[1].merge(Release::parse_entry(entry).unwrap());
+        assert!(changelog.render().contains(
+            "## [1.0.0] - 2024-05-01\n\nFirst release.\n\n```markdown\n## Not a release\n```\n\n### Fixed\n\n- A bug.\n- Another bug.\n\n### Changed\n\n- CI.\n\n[unreleased]:"
+        ));
+    }
+}
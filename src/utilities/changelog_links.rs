@@ -0,0 +1,139 @@
+/// ================================ ///
+///    UTILITIES :: Changelog links  ///
+/// ================================ ///
+/// Reference-style links of the CHANGELOG releases. Each release heading
+/// (`## [1.2.0]`) links to the comparison with the previous release, and the
+/// links are kept in a footer at the end of the CHANGELOG:
+///
+/// ```markdown
+/// [Unreleased]: https://github.com/owner/repo/compare/v1.2.0...HEAD
+/// [1.2.0]: https://github.com/owner/repo/compare/v1.1.0...v1.2.0
+/// [1.1.0]: https://github.com/owner/repo/releases/tag/v1.1.0
+/// ```
+// Local imports
+use crate::utilities::changelog::{is_link_definition, Changelog};
+use crate::utilities::{Config, VersionWiseError};
+
+/// Label of the section with the changes that are not released yet
+const UNRELEASED: &str = "Unreleased";
+
+/// Git host of the repository, which decides the shape of the URLs
+enum GitHost {
+    /// GitHub, and the hosts with the same URLs (Gitea, Forgejo, ...)
+    GitHub,
+    GitLab,
+    Bitbucket,
+}
+
+impl GitHost {
+    fn from_url(url: &str) -> GitHost {
+        if url.contains("gitlab") {
+            GitHost::GitLab
+        } else if url.contains("bitbucket") {
+            GitHost::Bitbucket
+        } else {
+            GitHost::GitHub
+        }
+    }
+
+    /// URL of the changes between two references
+    fn compare_url(&self, repository: &str, from: &str, to: &str) -> String {
+        match self {
+            GitHost::GitHub => format!("{}/compare/{}...{}", repository, from, to),
+            GitHost::GitLab => format!("{}/-/compare/{}...{}", repository, from, to),
+            GitHost::Bitbucket => format!("{}/branches/compare/{}%0D{}", repository, to, from),
+        }
+    }
+
+    /// URL of a tag, used for the first release
+    fn tag_url(&self, repository: &str, tag: &str) -> String {
+        match self {
+            GitHost::GitHub => format!("{}/releases/tag/{}", repository, tag),
+            GitHost::GitLab => format!("{}/-/tags/{}", repository, tag),
+            GitHost::Bitbucket => format!("{}/src/{}", repository, tag),
+        }
+    }
+}
+
+/// Whether the line is the link definition of one of the labels
+fn is_link_of(line: &str, labels: &[String]) -> bool {
+    line.strip_prefix('[')
+        .and_then(|rest| rest.split_once("]:"))
+        .is_some_and(|(label, _)| labels.iter().any(|l| l.eq_ignore_ascii_case(label)))
+}
+
+/// URL of the repository. With `compare_links`, the configuration already
+/// read it from the `origin` remote when it isn't set
+fn repository_url(config: &Config) -> Result<String, VersionWiseError> {
+    config.repository.clone().ok_or_else(|| {
+        VersionWiseError::Config(
+            "Couldn't find the repository for the compare links. Set `repository` in the configuration."
+                .to_string(),
+        )
+    })
+}
+
+/// URL of the changes of a release since the `previous` one. Without a
+/// `version`, it's the URL of the changes that are not released yet
+pub fn compare_link(
+    config: &Config,
+    previous: &str,
+    version: Option<&str>,
+) -> Result<String, VersionWiseError> {
+    let repository = repository_url(config)?;
+    let to = match version {
+        Some(version) => format!("{}{}", config.tag_prefix, version),
+        None => "HEAD".to_string(),
+    };
+    Ok(GitHost::from_url(&repository).compare_url(
+        &repository,
+        &format!("{}{}", config.tag_prefix, previous),
+        &to,
+    ))
+}
+
+/// Rewrite the footer of links of the CHANGELOG, with one link for each release
+/// heading. Other link definitions are kept
+pub fn update_links(config: &Config, changelog: &mut Changelog) -> Result<(), VersionWiseError> {
+    let labels: Vec<String> = changelog
+        .releases
+        .iter()
+        .filter_map(|release| release.link_label())
+        .map(|label| label.to_string())
+        .collect();
+    if labels.is_empty() {
+        return Ok(());
+    }
+    let repository = repository_url(config)?;
+    let host = GitHost::from_url(&repository);
+    let tag = |version: &str| format!("{}{}", config.tag_prefix, version);
+    // Remove the previous links, and write them again at the end
+    let mut footer: Vec<String> = changelog
+        .footer
+        .iter()
+        .filter(|line| is_link_definition(line) && !is_link_of(line, &labels))
+        .cloned()
+        .collect();
+    changelog.end_with_blank_line();
+    let releases: Vec<&String> = labels
+        .iter()
+        .filter(|label| !label.eq_ignore_ascii_case(UNRELEASED))
+        .collect();
+    for label in &labels {
+        let link = match releases.iter().position(|release| *release == label) {
+            // The changes since the latest release
+            None => match releases.first() {
+                Some(latest) => host.compare_url(&repository, &tag(latest), "HEAD"),
+                None => continue,
+            },
+            // The changes since the previous release, or the tag of the first one
+            Some(index) => match releases.get(index + 1) {
+                Some(previous) => host.compare_url(&repository, &tag(previous), &tag(label)),
+                None => host.tag_url(&repository, &tag(label)),
+            },
+        };
+        footer.push(format!("[{}]: {}", label, link));
+    }
+    changelog.footer = footer;
+    Ok(())
+}
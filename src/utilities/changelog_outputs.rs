@@ -0,0 +1,666 @@
+/// ================================ ///
+///   UTILITIES :: Changelog outputs ///
+/// ================================ ///
+/// Additional CHANGELOG files in other formats. Each release is rendered from
+/// the same sections as the markdown CHANGELOG, and inserted before the
+/// previous releases of the file, so its history is kept. The changesets
+/// released without a version bump join the release of the current version:
+///
+/// ```toml
+/// [[tool.versionwise.changelog_outputs]]
+/// path = "docs/changelog.rst"
+/// format = "rst"  # `markdown`, `rst`, `asciidoc`, `html` or `json`
+/// ```
+use serde::Serialize;
+use std::fs;
+use std::io;
+// Local imports
+use crate::options::Changeset;
+use crate::utilities::changelog::{Changelog, Release};
+use crate::utilities::changelog_utils::{
+    change_text, changelog_sections, release_entry, EntryDetails,
+};
+use crate::utilities::config::{ChangelogOutput, OutputFormat};
+use crate::utilities::{Config, VersionWiseError};
+
+/// Content of a new file of each format, before the first release
+fn empty_document(format: OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Markdown => "# Changelog\n",
+        OutputFormat::Rst => "Changelog\n=========\n\n",
+        OutputFormat::AsciiDoc => "= Changelog\n\n",
+        OutputFormat::Html => "<!DOCTYPE html>\n<html>\n<head>\n  <meta charset=\"utf-8\">\n  <title>Changelog</title>\n</head>\n<body>\n  <h1>Changelog</h1>\n</body>\n</html>\n",
+        OutputFormat::Json => "{\n  \"releases\": []\n}\n",
+    }
+}
+
+/// Escape the text written in the HTML output
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A release in the JSON output
+#[derive(Serialize)]
+struct JsonRelease<'a> {
+    version: &'a str,
+    date: String,
+    sections: Vec<JsonSection<'a>>,
+}
+
+/// A section of a release in the JSON output
+#[derive(Serialize)]
+struct JsonSection<'a> {
+    name: String,
+    changes: Vec<JsonChange<'a>>,
+}
+
+/// A change of a section in the JSON output
+#[derive(Serialize)]
+struct JsonChange<'a> {
+    change_type: &'a str,
+    tag: &'a str,
+    modules: Vec<&'a str>,
+    message: &'a str,
+}
+
+/// Write a change in a text format
+fn render_item(format: OutputFormat, changeset: &Changeset) -> String {
+    match format {
+        OutputFormat::Rst => format!("- {}\n", change_text(changeset)),
+        OutputFormat::AsciiDoc => format!("* {}\n", change_text(changeset)),
+        OutputFormat::Html => format!("      <li>{}</li>\n", escape_html(&change_text(changeset))),
+        OutputFormat::Markdown | OutputFormat::Json => {
+            unreachable!("The markdown and JSON entries are written as a whole")
+        }
+    }
+}
+
+/// Write a section of a release, with its changes, in a text format
+fn render_section(format: OutputFormat, section: &str, changesets: &[&Changeset]) -> String {
+    let items: String = changesets
+        .iter()
+        .map(|changeset| render_item(format, changeset))
+        .collect();
+    match format {
+        OutputFormat::Rst => format!(
+            "\n{}\n{}\n\n{}",
+            section,
+            "~".repeat(section.chars().count()),
+            items
+        ),
+        OutputFormat::AsciiDoc => format!("\n=== {}\n\n{}", section, items),
+        OutputFormat::Html => format!(
+            "    <h3>{}</h3>\n    <ul>\n{}    </ul>\n",
+            escape_html(section),
+            items
+        ),
+        OutputFormat::Markdown | OutputFormat::Json => {
+            unreachable!("The markdown and JSON entries are written as a whole")
+        }
+    }
+}
+
+/// Write the entry of a release in the given format
+fn render_entry(
+    config: &Config,
+    format: OutputFormat,
+    changesets: &[Changeset],
+    version: &str,
+    details: &EntryDetails,
+) -> Result<String, VersionWiseError> {
+    // The markdown is the same entry of the CHANGELOG
+    if format == OutputFormat::Markdown {
+        return Ok(release_entry(config, changesets, version, details)?.join(""));
+    }
+    let date = details.date.clone();
+    let title = format!("{} ({})", version, date);
+    let sections = changelog_sections(config, changesets);
+    let mut entry = String::new();
+    match format {
+        OutputFormat::Rst => {
+            entry.push_str(&format!(
+                "{}\n{}\n",
+                title,
+                "-".repeat(title.chars().count())
+            ));
+            for (section, grouped) in sections {
+                entry.push_str(&render_section(format, &section, &grouped));
+            }
+            entry.push('\n');
+        }
+        OutputFormat::AsciiDoc => {
+            entry.push_str(&format!("== {}\n", title));
+            for (section, grouped) in sections {
+                entry.push_str(&render_section(format, &section, &grouped));
+            }
+            entry.push('\n');
+        }
+        OutputFormat::Html => {
+            entry.push_str(&format!(
+                "  <section class=\"release\" id=\"{}{}\">\n",
+                config.tag_prefix,
+                escape_html(version)
+            ));
+            entry.push_str(&format!(
+                "    <h2>{} <time datetime=\"{}\">{}</time></h2>\n",
+                escape_html(version),
+                date,
+                date
+            ));
+            for (section, grouped) in sections {
+                entry.push_str(&render_section(format, &section, &grouped));
+            }
+            entry.push_str("  </section>\n");
+        }
+        OutputFormat::Json => {
+            let release = JsonRelease {
+                version,
+                date,
+                sections: sections
+                    .into_iter()
+                    .map(|(name, grouped)| JsonSection {
+                        name,
+                        changes: grouped
+                            .into_iter()
+                            .map(|changeset| JsonChange {
+                                change_type: changeset.change.trim(),
+                                tag: &changeset.tag,
+                                modules: changeset
+                                    .modules
+                                    .split(", ")
+                                    .filter(|module| !module.is_empty())
+                                    .collect(),
+                                message: &changeset.message,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            };
+            entry = serde_json::to_string_pretty(&release)
+                .map_err(|e| VersionWiseError::parse("<json output>", e))?;
+        }
+        OutputFormat::Markdown => unreachable!("The markdown entry is already written"),
+    }
+    Ok(entry)
+}
+
+/// Insert the entry before the line of the first release. Without releases,
+/// it goes before the `end` line, or at the end of the document
+fn insert_before(
+    content: &str,
+    entry: &str,
+    is_release: impl Fn(&[&str], usize) -> bool,
+    end: Option<&str>,
+) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let index = (0..lines.len())
+        .find(|index| is_release(&lines, *index))
+        .or_else(|| end.and_then(|end| lines.iter().position(|line| line.trim() == end)))
+        .unwrap_or(lines.len());
+    let mut before = lines[..index].join("\n");
+    // Keep a blank line between the entry and the text above it, except in HTML
+    if !before.is_empty() {
+        before.push('\n');
+        if end.is_none() && !before.ends_with("\n\n") {
+            before.push('\n');
+        }
+    }
+    let after = lines[index..].join("\n");
+    // And end the document with a single newline
+    let updated = format!("{}{}{}", before, entry, after);
+    format!("{}\n", updated.trim_end_matches('\n'))
+}
+
+/// Whether the line starts a release of a text format
+fn is_release(format: OutputFormat, lines: &[&str], index: usize) -> bool {
+    match format {
+        // A release is a title underlined with `-`
+        OutputFormat::Rst => {
+            let title = lines[index].trim_end();
+            lines.get(index + 1).is_some_and(|underline| {
+                let underline = underline.trim_end();
+                !title.is_empty()
+                    && !title.chars().all(|c| c == '-')
+                    && underline.chars().all(|c| c == '-')
+                    && underline.chars().count() >= title.chars().count()
+            })
+        }
+        OutputFormat::AsciiDoc => lines[index].starts_with("== "),
+        OutputFormat::Html => lines[index].contains("<section class=\"release\""),
+        OutputFormat::Markdown | OutputFormat::Json => false,
+    }
+}
+
+/// Whether the line starts the release of `version`, whatever its date
+fn is_release_of(config: &Config, format: OutputFormat, line: &str, version: &str) -> bool {
+    match format {
+        OutputFormat::Rst => line.starts_with(&format!("{} (", version)),
+        OutputFormat::AsciiDoc => line.starts_with(&format!("== {} (", version)),
+        OutputFormat::Html => line.contains(&format!(
+            "id=\"{}{}\"",
+            config.tag_prefix,
+            escape_html(version)
+        )),
+        OutputFormat::Markdown | OutputFormat::Json => false,
+    }
+}
+
+/// Name of the section that starts in the line, in a text format
+fn section_name(format: OutputFormat, lines: &[&str], index: usize) -> Option<String> {
+    let line = lines[index].trim_end();
+    match format {
+        // A section is a title underlined with `~`
+        OutputFormat::Rst => lines
+            .get(index + 1)
+            .map(|underline| underline.trim_end())
+            .filter(|underline| {
+                !line.is_empty() && !underline.is_empty() && underline.chars().all(|c| c == '~')
+            })
+            .map(|_| line.to_string()),
+        OutputFormat::AsciiDoc => line.strip_prefix("=== ").map(|name| name.to_string()),
+        OutputFormat::Html => line
+            .trim()
+            .strip_prefix("<h3>")
+            .and_then(|name| name.strip_suffix("</h3>"))
+            .map(|name| name.to_string()),
+        OutputFormat::Markdown | OutputFormat::Json => None,
+    }
+}
+
+/// Position after the last non-blank line between `start` and `end`
+fn after_content(lines: &[&str], start: usize, end: usize) -> usize {
+    (start..end)
+        .rfind(|index| !lines[*index].trim().is_empty())
+        .map_or(end, |index| index + 1)
+}
+
+/// Add the sections of a release to the release of the same version of a
+/// text output. The changes of a section that already exists go after its
+/// changes, and the new sections go at the end of the release.
+/// Returns `None` if the output doesn't have that release
+fn merge_text(
+    config: &Config,
+    format: OutputFormat,
+    content: &str,
+    changesets: &[Changeset],
+    version: &str,
+) -> Option<String> {
+    let mut lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+    let refs: Vec<&str> = lines.iter().map(|line| line.as_str()).collect();
+    let start = (0..refs.len()).find(|index| {
+        is_release(format, &refs, *index) && is_release_of(config, format, refs[*index], version)
+    })?;
+    for (section, grouped) in changelog_sections(config, changesets) {
+        let refs: Vec<&str> = lines.iter().map(|line| line.as_str()).collect();
+        // The release ends at its `</section>`, or before the next release
+        let end = match format {
+            OutputFormat::Html => (start..refs.len())
+                .find(|index| refs[*index].trim() == "</section>")
+                .unwrap_or(refs.len()),
+            _ => (start + 1..refs.len())
+                .find(|index| is_release(format, &refs, *index))
+                .unwrap_or(refs.len()),
+        };
+        let headings: Vec<(usize, String)> = (start + 1..end)
+            .filter_map(|index| section_name(format, &refs, index).map(|name| (index, name)))
+            .collect();
+        // The HTML headings are escaped
+        let name = match format {
+            OutputFormat::Html => escape_html(&section),
+            _ => section.clone(),
+        };
+        let heading = headings.iter().position(|(_, heading)| *heading == name);
+        let (index, text) = match heading {
+            Some(position) => {
+                let heading = headings[position].0;
+                let next = headings.get(position + 1).map_or(end, |(index, _)| *index);
+                let index = match format {
+                    OutputFormat::Html => (heading..next)
+                        .find(|index| refs[*index].trim() == "</ul>")
+                        .unwrap_or(next),
+                    _ => after_content(&refs, heading, next),
+                };
+                let items: String = grouped
+                    .iter()
+                    .map(|changeset| render_item(format, changeset))
+                    .collect();
+                (index, items)
+            }
+            None => {
+                let index = match format {
+                    OutputFormat::Html => end,
+                    _ => after_content(&refs, start, end),
+                };
+                (index, render_section(format, &section, &grouped))
+            }
+        };
+        lines.splice(index..index, text.lines().map(|line| line.to_string()));
+    }
+    Some(format!("{}\n", lines.join("\n").trim_end_matches('\n')))
+}
+
+/// Add a release to the release of the same version of the output, instead
+/// of writing it twice. Returns `None` if the output doesn't have that release
+fn merge_entry(
+    config: &Config,
+    path: &str,
+    format: OutputFormat,
+    content: &str,
+    changesets: &[Changeset],
+    version: &str,
+    entry: &str,
+) -> Result<Option<String>, VersionWiseError> {
+    match format {
+        OutputFormat::Markdown => {
+            let mut changelog = Changelog::parse(content);
+            let Some(index) = changelog.release_index(version) else {
+                return Ok(None);
+            };
+            changelog.releases[index].merge(Release::parse_entry(entry)?);
+            Ok(Some(changelog.render()))
+        }
+        // The changes of a section that already exists go after its changes
+        OutputFormat::Json => {
+            let mut document: serde_json::Value =
+                serde_json::from_str(content).map_err(|e| VersionWiseError::parse(path, e))?;
+            let release: serde_json::Value =
+                serde_json::from_str(entry).map_err(|e| VersionWiseError::parse(path, e))?;
+            let Some(existing) = document
+                .get_mut("releases")
+                .and_then(|releases| releases.as_array_mut())
+                .and_then(|releases| {
+                    releases
+                        .iter_mut()
+                        .find(|existing| existing["version"] == release["version"])
+                })
+            else {
+                return Ok(None);
+            };
+            let sections = existing
+                .get_mut("sections")
+                .and_then(|sections| sections.as_array_mut())
+                .ok_or_else(|| {
+                    VersionWiseError::parse(
+                        path,
+                        format!("the release `{}` should have a `sections` list", version),
+                    )
+                })?;
+            for section in release["sections"].as_array().into_iter().flatten() {
+                match sections
+                    .iter_mut()
+                    .find(|existing| existing["name"] == section["name"])
+                    .and_then(|existing| existing["changes"].as_array_mut())
+                {
+                    Some(changes) => {
+                        changes.extend(section["changes"].as_array().into_iter().flatten().cloned())
+                    }
+                    None => sections.push(section.clone()),
+                }
+            }
+            let mut updated = serde_json::to_string_pretty(&document)
+                .map_err(|e| VersionWiseError::parse(path, e))?;
+            updated.push('\n');
+            Ok(Some(updated))
+        }
+        _ => Ok(merge_text(config, format, content, changesets, version)),
+    }
+}
+
+/// Insert the entry of a release in the content of the output
+fn insert_entry(
+    path: &str,
+    format: OutputFormat,
+    content: &str,
+    entry: &str,
+) -> Result<String, VersionWiseError> {
+    let updated = match format {
+        OutputFormat::Markdown => {
+            let mut changelog = Changelog::parse(content);
+            changelog.insert_release(Release::parse_entry(entry)?, false);
+            changelog.render()
+        }
+        OutputFormat::Rst | OutputFormat::AsciiDoc => insert_before(
+            content,
+            entry,
+            |lines, index| is_release(format, lines, index),
+            None,
+        ),
+        OutputFormat::Html => insert_before(
+            content,
+            entry,
+            |lines, index| is_release(format, lines, index),
+            Some("</body>"),
+        ),
+        // The releases are a list, from the newest one
+        OutputFormat::Json => {
+            let mut document: serde_json::Value =
+                serde_json::from_str(content).map_err(|e| VersionWiseError::parse(path, e))?;
+            let release =
+                serde_json::from_str(entry).map_err(|e| VersionWiseError::parse(path, e))?;
+            let releases = document
+                .get_mut("releases")
+                .and_then(|releases| releases.as_array_mut())
+                .ok_or_else(|| {
+                    VersionWiseError::parse(path, "it should be an object with a `releases` list")
+                })?;
+            releases.insert(0, release);
+            let mut updated = serde_json::to_string_pretty(&document)
+                .map_err(|e| VersionWiseError::parse(path, e))?;
+            updated.push('\n');
+            updated
+        }
+    };
+    Ok(updated)
+}
+
+/// Entry of a release in an additional CHANGELOG file, as it'd be written
+pub fn output_entry(
+    config: &Config,
+    output: &ChangelogOutput,
+    changesets: &[Changeset],
+    version: &str,
+    details: &EntryDetails,
+) -> Result<String, VersionWiseError> {
+    render_entry(config, output.format, changesets, version, details)
+}
+
+/// Render a release in every additional CHANGELOG file, without writing them.
+/// Returns the path and the updated content of each file. The files that
+/// don't exist yet start from an empty document. With `merge`, a release of
+/// the same version gets the new changes, instead of a second release
+pub fn render_changelog_outputs(
+    config: &Config,
+    changesets: &[Changeset],
+    version: &str,
+    details: &EntryDetails,
+    merge: bool,
+) -> Result<Vec<(String, String)>, VersionWiseError> {
+    let mut files = Vec::new();
+    for output in &config.changelog_outputs {
+        let path = &output.path;
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                empty_document(output.format).to_string()
+            }
+            Err(e) => return Err(VersionWiseError::io(path, e)),
+        };
+        let entry = output_entry(config, output, changesets, version, details)?;
+        let merged = match merge {
+            true => merge_entry(
+                config,
+                path,
+                output.format,
+                &content,
+                changesets,
+                version,
+                &entry,
+            )?,
+            false => None,
+        };
+        let updated = match merged {
+            Some(updated) => updated,
+            None => insert_entry(path, output.format, &content, &entry)?,
+        };
+        files.push((path.clone(), updated));
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMATS: [OutputFormat; 5] = [
+        OutputFormat::Markdown,
+        OutputFormat::Rst,
+        OutputFormat::AsciiDoc,
+        OutputFormat::Html,
+        OutputFormat::Json,
+    ];
+
+    fn config() -> Config {
+        Config::from_toml(
+            "version_path = [\"pyproject.toml\"]\n\n[[tags]]\nname = \"Bug\"\nbump = \"patch\"\n\n[[tags]]\nname = \"Docs\"\nbump = \"none\"",
+        )
+    }
+
+    fn changeset(change: &str, tag: &str, message: &str) -> Changeset {
+        Changeset::new(
+            message.to_lowercase(),
+            change.to_string(),
+            "src".to_string(),
+            tag.to_string(),
+            message.to_string(),
+            "1.0.0".to_string(),
+        )
+    }
+
+    fn details(date: &str) -> EntryDetails {
+        EntryDetails {
+            date: date.to_string(),
+            previous_version: "0.9.0".to_string(),
+            changeset_dir: ".changesets".to_string(),
+        }
+    }
+
+    /// Output with the releases `0.9.0` and `1.0.0`, and then the changes
+    /// released later without a version bump
+    fn released_twice(format: OutputFormat) -> String {
+        let config = config();
+        let mut content = empty_document(format).to_string();
+        for (version, message) in [("0.9.0", "Old"), ("1.0.0", "First")] {
+            let changesets = [changeset("patch", "Bug", message)];
+            let entry = render_entry(
+                &config,
+                format,
+                &changesets,
+                version,
+                &details("2024-05-01"),
+            );
+            content = insert_entry("out", format, &content, &entry.unwrap()).unwrap();
+        }
+        let changesets = [
+            changeset("none", "Docs", "Guide"),
+            changeset("patch", "Bug", "Second"),
+        ];
+        let entry = render_entry(
+            &config,
+            format,
+            &changesets,
+            "1.0.0",
+            &details("2024-06-01"),
+        );
+        merge_entry(
+            &config,
+            "out",
+            format,
+            &content,
+            &changesets,
+            "1.0.0",
+            &entry.unwrap(),
+        )
+        .unwrap()
+        .expect("the release should exist")
+    }
+
+    #[test]
+    fn merges_the_markdown_release() {
+        let expected = "# Changelog\n\n## [1.0.0]\n\n### Bug\n\n- src: First.\n- src: Second.\n\n### Docs\n\n- src: Guide.\n\n## [0.9.0]\n\n### Bug\n\n- src: Old.\n";
+        assert_eq!(released_twice(OutputFormat::Markdown), expected);
+    }
+
+    #[test]
+    fn merges_the_rst_release() {
+        let expected = "Changelog\n=========\n\n1.0.0 (2024-05-01)\n------------------\n\nBug\n~~~\n\n- src: First.\n- src: Second.\n\nDocs\n~~~~\n\n- src: Guide.\n\n0.9.0 (2024-05-01)\n------------------\n\nBug\n~~~\n\n- src: Old.\n";
+        assert_eq!(released_twice(OutputFormat::Rst), expected);
+    }
+
+    #[test]
+    fn merges_the_asciidoc_release() {
+        let expected = "= Changelog\n\n== 1.0.0 (2024-05-01)\n\n=== Bug\n\n* src: First.\n* src: Second.\n\n=== Docs\n\n* src: Guide.\n\n== 0.9.0 (2024-05-01)\n\n=== Bug\n\n* src: Old.\n";
+        assert_eq!(released_twice(OutputFormat::AsciiDoc), expected);
+    }
+
+    #[test]
+    fn merges_the_html_release() {
+        let output = released_twice(OutputFormat::Html);
+        let expected = "  <section class=\"release\" id=\"v1.0.0\">\n    <h2>1.0.0 <time datetime=\"2024-05-01\">2024-05-01</time></h2>\n    <h3>Bug</h3>\n    <ul>\n      <li>src: First.</li>\n      <li>src: Second.</li>\n    </ul>\n    <h3>Docs</h3>\n    <ul>\n      <li>src: Guide.</li>\n    </ul>\n  </section>\n  <section class=\"release\" id=\"v0.9.0\">\n";
+        assert!(output.contains(expected), "{}", output);
+        assert_eq!(output.matches("<section").count(), 2);
+        assert!(output.ends_with("  </section>\n</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn merges_the_json_release() {
+        let output: serde_json::Value =
+            serde_json::from_str(&released_twice(OutputFormat::Json)).unwrap();
+        let releases = output["releases"].as_array().unwrap();
+        assert_eq!(releases.len(), 2);
+        assert_eq!(releases[0]["version"], "1.0.0");
+        assert_eq!(releases[0]["date"], "2024-05-01");
+        let sections = releases[0]["sections"].as_array().unwrap();
+        let messages = |index: usize| -> Vec<&str> {
+            sections[index]["changes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|change| change["message"].as_str().unwrap())
+                .collect()
+        };
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0]["name"], "Bug");
+        assert_eq!(messages(0), ["First", "Second"]);
+        assert_eq!(sections[1]["name"], "Docs");
+        assert_eq!(messages(1), ["Guide"]);
+    }
+
+    #[test]
+    fn only_merges_an_existing_release() {
+        let config = config();
+        let changesets = [changeset("none", "Docs", "Guide")];
+        for format in FORMATS {
+            let content = empty_document(format);
+            let entry = render_entry(
+                &config,
+                format,
+                &changesets,
+                "1.0.0",
+                &details("2024-06-01"),
+            );
+            let merged = merge_entry(
+                &config,
+                "out",
+                format,
+                content,
+                &changesets,
+                "1.0.0",
+                &entry.unwrap(),
+            );
+            assert_eq!(merged.unwrap(), None, "{:?}", format);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+/// ================================ ///
+///  UTILITIES :: Changelog template ///
+/// ================================ ///
+/// User-defined layout of the CHANGELOG entries, set with `changelog_template`.
+/// The template uses the Tera syntax (https://keats.github.io/tera/docs/), and
+/// it's rendered with:
+///
+/// * `version`: version of the release. Empty for the `Unreleased` section.
+/// * `previous_version`: version before the release. Empty for the first one.
+/// * `date`: date of the release (`2024-05-01`).
+/// * `unreleased`: whether it's the `Unreleased` section.
+/// * `link`: URL of the changes since the previous version, when the
+///   `repository` is configured or `compare_links` is enabled, and there's a
+///   previous version.
+/// * `sections`: list of `{ name, changes }`, grouped like the CHANGELOG.
+/// * `changes`: every change, with its `name`, `change_type`, `tag`,
+///   `section`, `modules`, `message` and `authors`.
+/// * `authors`: authors of every change.
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use tera::{Context, Tera};
+// Local imports
+use crate::options::Changeset;
+use crate::utilities::changelog_links::compare_link;
+use crate::utilities::changelog_utils::{changelog_sections, EntryDetails};
+use crate::utilities::git_utils::get_file_authors;
+use crate::utilities::{Config, VersionWiseError};
+
+/// Name of the template inside of Tera
+const TEMPLATE_NAME: &str = "changelog";
+
+/// A change, as it's seen by the template
+#[derive(Serialize)]
+struct TemplateChange<'a> {
+    name: &'a str,
+    change_type: &'a str,
+    tag: &'a str,
+    section: &'a str,
+    modules: Vec<&'a str>,
+    message: &'a str,
+    authors: Vec<String>,
+}
+
+/// A section of the entry, as it's seen by the template
+#[derive(Serialize)]
+struct TemplateSection<'a> {
+    name: &'a str,
+    changes: Vec<TemplateChange<'a>>,
+}
+
+/// Describe a Tera error with all of its causes, since the first one only
+/// says which template failed
+fn describe(error: &tera::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+/// Read and compile the template file. Returns the reason if it's invalid
+pub fn load_template(path: &str) -> Result<Tera, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("cannot read `{}` ({})", path, e))?;
+    let mut tera = Tera::default();
+    tera.add_raw_template(TEMPLATE_NAME, &content)
+        .map_err(|e| describe(&e))?;
+    Ok(tera)
+}
+
+/// Render the entry of a release, or of the `Unreleased` section when there's no `version`
+pub fn render_template(
+    config: &Config,
+    template: &Tera,
+    changesets: &[Changeset],
+    version: Option<&str>,
+    details: &EntryDetails,
+) -> Result<String, VersionWiseError> {
+    let sections = changelog_sections(config, changesets);
+    // Build the changes of each section
+    let sections: Vec<TemplateSection> = sections
+        .iter()
+        .map(|(section, grouped)| TemplateSection {
+            name: section,
+            changes: grouped
+                .iter()
+                .map(|changeset| TemplateChange {
+                    name: &changeset.name,
+                    change_type: changeset.change.trim(),
+                    tag: &changeset.tag,
+                    section,
+                    modules: changeset
+                        .modules
+                        .split(", ")
+                        .filter(|module| !module.is_empty())
+                        .collect(),
+                    message: changeset.message.trim(),
+                    authors: get_file_authors(&format!(
+                        "{}/{}.toml",
+                        details.changeset_dir, changeset.name
+                    )),
+                })
+                .collect(),
+        })
+        .collect();
+    let changes: Vec<&TemplateChange> = sections.iter().flat_map(|s| &s.changes).collect();
+    let mut authors: Vec<&String> = Vec::new();
+    for author in changes.iter().flat_map(|change| &change.authors) {
+        if !authors.contains(&author) {
+            authors.push(author);
+        }
+    }
+    // The link is only available when the repository is known
+    let previous_version = &details.previous_version;
+    let link = match config.repository.is_some() {
+        true if !previous_version.is_empty() => {
+            Some(compare_link(config, previous_version, version)?)
+        }
+        _ => None,
+    };
+    let mut context = Context::new();
+    context.insert("version", version.unwrap_or_default());
+    context.insert("previous_version", previous_version);
+    context.insert("date", &details.date);
+    context.insert("unreleased", &version.is_none());
+    context.insert("link", &link);
+    context.insert("sections", &sections);
+    context.insert("changes", &changes);
+    context.insert("authors", &authors);
+    let rendered = template.render(TEMPLATE_NAME, &context).map_err(|e| {
+        VersionWiseError::Config(format!(
+            "Cannot render the `changelog_template`: {}",
+            describe(&e)
+        ))
+    })?;
+    // Keep a blank line between the entries
+    Ok(format!("{}\n", rendered.trim()))
+}
@@ -1,95 +1,401 @@
-use std::collections::HashSet;
+use std::cmp::Ordering;
 use std::fs;
-use std::io::{BufRead, BufReader, Write};
+use std::io;
+use std::path::Path;
 // Local imports
 use crate::options::Changeset;
-use crate::utilities::update_version_path;
+use crate::utilities::changelog::{Changelog, Release};
+use crate::utilities::changelog_links::update_links;
+use crate::utilities::changelog_template::render_template;
+use crate::utilities::config::{ChangelogFormat, ARCHIVE_DIR};
+use crate::utilities::date::Date;
+use crate::utilities::git_utils::get_added_date;
+use crate::utilities::pre_release::PRE_STATE_FILE;
+use crate::utilities::version_operations::ChangeType;
+use crate::utilities::{
+    find_version, get_archived_changesets, get_current_changesets, next_release, Config,
+    VersionWiseError,
+};
+
+/// Sections of Keep a Changelog, in the order they're written
+const KEEP_A_CHANGELOG_SECTIONS: [&str; 6] = [
+    "Added",
+    "Changed",
+    "Deprecated",
+    "Removed",
+    "Fixed",
+    "Security",
+];
+
+/// Heading of the section with the changes that are not released yet
+const UNRELEASED_HEADING: &str = "## [Unreleased]";
 
 /// Function to open the Changeset in case that exists
-pub fn open_changelog() -> Vec<String> {
+pub fn open_changelog(config: &Config) -> Result<Changelog, VersionWiseError> {
     // Open the Changeset file in case that exist
-    let file = fs::File::open("CHANGELOG.md")
-        .expect("Error opening CHANGELOG.md. Ensure that you have one already.");
-    let reader = BufReader::new(file);
+    let path = &config.changelog;
+    let content = fs::read_to_string(path).map_err(|e| {
+        VersionWiseError::io(
+            path,
+            io::Error::new(
+                e.kind(),
+                format!("{}. Ensure that you have one already.", e),
+            ),
+        )
+    })?;
+    // And return it parsed
+    Ok(Changelog::parse(&content))
+}
 
-    // Create the content structure
-    let mut content: Vec<String> = Vec::new();
+/// Render the entire CHANGELOG content, without writing it
+pub fn render_changelog(
+    config: &Config,
+    changelog: &mut Changelog,
+) -> Result<String, VersionWiseError> {
+    // Keep the links of the releases up to date
+    if config.compare_links {
+        update_links(config, changelog)?;
+    }
+    Ok(changelog.render())
+}
+
+/// Write the entire CHANGELOG content
+pub fn write_changelog(config: &Config, changelog: &mut Changelog) -> Result<(), VersionWiseError> {
+    let path = &config.changelog;
+    let content = render_changelog(config, changelog)?;
+    fs::write(path, content).map_err(|e| VersionWiseError::io(path, e))
+}
+
+/// A release of the rebuilt CHANGELOG
+enum RebuiltRelease {
+    /// Release of the CHANGELOG without archived changesets, kept as it is
+    Kept(Release),
+    /// Release written again from its archived changesets
+    Archived(String, Vec<Changeset>),
+}
 
-    reader.lines().for_each(|line| {
-        if let Ok(line_content) = line {
-            content.push(line_content);
+impl RebuiltRelease {
+    fn version(&self) -> &str {
+        match self {
+            RebuiltRelease::Kept(release) => &release.version,
+            RebuiltRelease::Archived(version, _) => version,
         }
-    });
-    // And return it
-    content
+    }
 }
 
-pub fn create_changelog(content: Vec<String>, version: &str) {
-    // Create a new CHANGELOG.md file
-    let mut file = fs::File::create("CHANGELOG.md").expect("Error creating the CHANGELOG.md");
+/// Write the CHANGELOG again from the archived changesets of every release,
+/// with the current format, template and tags. The releases without archived
+/// changesets (like the ones before `archive_changesets` was enabled) and the
+/// text around the releases are kept
+pub fn rebuild_changelog(config: &Config) -> Result<Changelog, VersionWiseError> {
+    let archived = get_archived_changesets(config)?;
+    if archived.is_empty() {
+        return Err(VersionWiseError::Config(format!(
+            "There are no archived changesets in `{}` to rebuild the `{}` from. Set `archive_changesets = true` so `bump` keeps them.",
+            config.changeset_path(ARCHIVE_DIR),
+            config.changelog
+        )));
+    }
+    let previous = match fs::metadata(&config.changelog) {
+        Ok(_) => open_changelog(config)?,
+        Err(_) => Changelog::parse("# Changelog\n"),
+    };
+    let scheme = &config.version_scheme;
+    // Keep the releases that are not archived. The Unreleased section is
+    // written again if VersionWise keeps it
+    let mut releases: Vec<RebuiltRelease> = previous
+        .releases
+        .iter()
+        .filter(|release| !(release.is_unreleased() && config.unreleased))
+        .filter(|release| {
+            !archived.iter().any(|(version, _)| {
+                matches!(
+                    scheme.compare(version, &release.version),
+                    Ok(Ordering::Equal)
+                )
+            })
+        })
+        .cloned()
+        .map(RebuiltRelease::Kept)
+        .collect();
+    // And place every archived release before the first older one
+    for (version, changesets) in archived {
+        let index = releases
+            .iter()
+            .position(|release| {
+                matches!(
+                    scheme.compare(&version, release.version()),
+                    Ok(Ordering::Greater)
+                )
+            })
+            .unwrap_or(releases.len());
+        releases.insert(index, RebuiltRelease::Archived(version, changesets));
+    }
+    // Write them again, keeping the date of each release when it's known
+    let mut changelog = previous.clone();
+    changelog.releases.clear();
+    for (index, release) in releases.iter().enumerate() {
+        let release = match release {
+            RebuiltRelease::Kept(release) => release.clone(),
+            RebuiltRelease::Archived(version, changesets) => {
+                let archive = config.archive_path(version);
+                let date = previous
+                    .release_index(version)
+                    .and_then(|index| previous.releases[index].date.clone())
+                    .or_else(|| get_added_date(&archive));
+                let details = EntryDetails {
+                    date: match date {
+                        Some(date) => date,
+                        None => Date::today()?.to_string(),
+                    },
+                    previous_version: releases
+                        .get(index + 1)
+                        .map(|release| release.version().to_string())
+                        .unwrap_or_default(),
+                    changeset_dir: archive,
+                };
+                let entry = release_entry(config, changesets, version, &details)?;
+                Release::parse_entry(&entry.join(""))?
+            }
+        };
+        changelog.insert_release_at(changelog.releases.len(), release);
+    }
+    if config.unreleased {
+        let pending = match next_release(config, get_current_changesets(config)?)? {
+            Some(release) => release.changesets,
+            None => Vec::new(),
+        };
+        changelog.set_unreleased(Release::parse_entry(
+            &unreleased_entry(config, &pending)?.join(""),
+        )?);
+    }
+    // The links are written here too, so a dry run shows them
+    if config.compare_links {
+        update_links(config, &mut changelog)?;
+    }
+    Ok(changelog)
+}
 
-    // Write the entire CHANGELOG content
-    writeln!(file, "{}", content.join("\n")).expect("Error when writing the CHANGELOG.md");
-    // Write the new version file too
-    update_version_path(version);
-    // Delete all the current changesets
-    delete_changesets();
-    // If everything's cool, then write the successful message
-    println!("The `CHANGELOG.md` and version has been updated!");
+/// Write the pending changesets in the `Unreleased` section of the CHANGELOG
+pub fn update_unreleased(config: &Config) -> Result<(), VersionWiseError> {
+    let changesets = match next_release(config, get_current_changesets(config)?)? {
+        Some(release) => release.changesets,
+        None => Vec::new(),
+    };
+    let mut changelog = open_changelog(config)?;
+    changelog.set_unreleased(Release::parse_entry(
+        &unreleased_entry(config, &changesets)?.join(""),
+    )?);
+    write_changelog(config, &mut changelog)
 }
 
-pub fn new_changelog_entry(changesets: &[Changeset], version: &String) -> Vec<String> {
-    // Update the version based on the latest
-    // First, get a list of printed tags to avoid read the same tag twice
-    let mut printed_tags: HashSet<&String> = HashSet::new();
-    // Create a mutable for the content written
-    let mut content: Vec<String> = Vec::new();
-    content.push(format!("## [{}]\n", version));
-    for changeset in changesets.iter() {
-        // Evaluate if this tag has been written
-        if printed_tags.contains(&changeset.tag) {
-            continue;
+/// Group the changesets by their CHANGELOG section. The sections follow the
+/// order of the configured tags, and the changesets keep their own order
+pub fn group_by_section<'a>(
+    config: &Config,
+    changesets: impl IntoIterator<Item = &'a Changeset>,
+) -> Vec<(String, Vec<&'a Changeset>)> {
+    let mut sections: Vec<(String, Vec<&'a Changeset>)> = Vec::new();
+    for changeset in changesets {
+        let section = config.section_of(changeset);
+        match sections.iter_mut().find(|(name, _)| *name == section) {
+            Some((_, grouped)) => grouped.push(changeset),
+            None => sections.push((section, vec![changeset])),
         }
-        // Write the tag first
-        content.push(format!("\n### {}\n\n", changeset.tag));
-        // Filter for all the same tags
-        for nested_changeset in changesets.iter().filter(|c| c.tag == changeset.tag) {
-            // Then, write all the changes
-            if nested_changeset.modules.is_empty() {
-                content.push(format!("- {}.\n", nested_changeset.message));
-            } else {
-                content.push(format!(
-                    "- {}: {}.\n",
-                    nested_changeset.modules, nested_changeset.message
-                ));
-            }
+    }
+    // The sort is stable, so the unknown sections keep their order at the end
+    sections.sort_by_key(|(section, _)| config.section_position(section));
+    sections
+}
+
+/// Keep a Changelog section of a changeset. The configured `section` is used
+/// when it's a standard one. Otherwise, it depends on the tag and the change type
+fn keep_a_changelog_section(config: &Config, changeset: &Changeset) -> &'static str {
+    let section = config.section_of(changeset);
+    if let Some(standard) = KEEP_A_CHANGELOG_SECTIONS
+        .iter()
+        .find(|standard| standard.eq_ignore_ascii_case(&section))
+    {
+        return standard;
+    }
+    match section.as_str() {
+        "Feature" | "Add" => "Added",
+        "Remove" => "Removed",
+        "Bug" | "Patch" => "Fixed",
+        "Rename" | "Behavior" | "Refactor" | "Optimization" | "Tests" => "Changed",
+        _ => match changeset.change.parse() {
+            Ok(ChangeType::Minor) => "Added",
+            Ok(ChangeType::Patch) => "Fixed",
+            _ => "Changed",
+        },
+    }
+}
+
+/// Group the changesets that go in the CHANGELOG by their section, depending
+/// on the format of the CHANGELOG
+pub fn changelog_sections<'a>(
+    config: &Config,
+    changesets: &'a [Changeset],
+) -> Vec<(String, Vec<&'a Changeset>)> {
+    let visible = changesets.iter().filter(|c| config.in_changelog(c));
+    match config.changelog_format {
+        ChangelogFormat::VersionWise => group_by_section(config, visible),
+        ChangelogFormat::KeepAChangelog => {
+            let visible: Vec<&Changeset> = visible.collect();
+            KEEP_A_CHANGELOG_SECTIONS
+                .iter()
+                .map(|section| {
+                    let grouped: Vec<&Changeset> = visible
+                        .iter()
+                        .copied()
+                        .filter(|c| keep_a_changelog_section(config, c) == *section)
+                        .collect();
+                    (section.to_string(), grouped)
+                })
+                .filter(|(_, grouped)| !grouped.is_empty())
+                .collect()
+        }
+    }
+}
+
+/// End a message with a period, unless it already has one
+fn with_period(message: &str) -> String {
+    let message = message.trim_end();
+    match message.ends_with(['.', '!', '?']) {
+        true => message.to_string(),
+        false => format!("{}.", message),
+    }
+}
+
+/// Text of a change in the CHANGELOG, with its modules, like `src/api.rs: Add the API.`
+pub fn change_text(changeset: &Changeset) -> String {
+    let message = with_period(&changeset.message);
+    match changeset.modules.is_empty() {
+        true => message,
+        false => format!("{}: {}", changeset.modules, message),
+    }
+}
+
+/// Write the sections of an entry, with the changesets that go in the CHANGELOG
+fn render_sections(config: &Config, changesets: &[Changeset]) -> Vec<String> {
+    let mut content: Vec<String> = Vec::new();
+    for (section, grouped) in changelog_sections(config, changesets) {
+        // Write the section first
+        content.push(format!("\n### {}\n\n", section));
+        // Then, write all the changes
+        for changeset in grouped {
+            content.push(format!("- {}\n", change_text(changeset)));
         }
-        // And at the end, write this tag on the read ones
-        printed_tags.insert(&changeset.tag);
     }
-    // And at the end, return the content list
     content
 }
 
-fn delete_changesets() {
-    let folder_path = ".changesets";
-    // Verify if the folder exist
-    if let Ok(entries) = fs::read_dir(folder_path) {
-        // Iterate over all the changesets in that folder
-        for entry in entries.flatten() {
-            let path = entry.path();
-            // For security, verify if the entry is a file
-            if path.is_file() {
-                // Try to remove the file
-                if let Err(e) = fs::remove_file(&path) {
-                    // If you could not delete a file, then panic
-                    panic!("Error deleting file {}: {}", path.display(), e);
-                }
-            }
+/// Details of a release that are not in its changesets
+pub struct EntryDetails {
+    /// Date of the release (`2024-05-01`)
+    pub date: String,
+    /// Version before the release. Empty for the first release
+    pub previous_version: String,
+    /// Folder where the changesets of the release are
+    pub changeset_dir: String,
+}
+
+impl EntryDetails {
+    /// Details of the release done now, from the current version and changesets
+    pub fn upcoming(config: &Config) -> Result<EntryDetails, VersionWiseError> {
+        Ok(EntryDetails {
+            date: Date::today()?.to_string(),
+            previous_version: find_version(config)?,
+            changeset_dir: config.changeset_dir.clone(),
+        })
+    }
+}
+
+/// Write the CHANGELOG entry of a release. Keep a Changelog entries include
+/// the release date
+pub fn new_changelog_entry(
+    config: &Config,
+    changesets: &[Changeset],
+    version: &str,
+) -> Result<Vec<String>, VersionWiseError> {
+    release_entry(
+        config,
+        changesets,
+        version,
+        &EntryDetails::upcoming(config)?,
+    )
+}
+
+/// Write the CHANGELOG entry of any release, like the past ones
+pub fn release_entry(
+    config: &Config,
+    changesets: &[Changeset],
+    version: &str,
+    details: &EntryDetails,
+) -> Result<Vec<String>, VersionWiseError> {
+    // A user-defined template writes the whole entry
+    if let Some(template) = &config.changelog_template {
+        return Ok(vec![render_template(
+            config,
+            template,
+            changesets,
+            Some(version),
+            details,
+        )?]);
+    }
+    let heading = match config.changelog_format {
+        ChangelogFormat::VersionWise => format!("## [{}]\n", version),
+        ChangelogFormat::KeepAChangelog => format!("## [{}] - {}\n", version, details.date),
+    };
+    let mut content = vec![heading];
+    content.extend(render_sections(config, changesets));
+    Ok(content)
+}
+
+/// Write the `Unreleased` section with the pending changesets
+pub fn unreleased_entry(
+    config: &Config,
+    changesets: &[Changeset],
+) -> Result<Vec<String>, VersionWiseError> {
+    if let Some(template) = &config.changelog_template {
+        let details = EntryDetails::upcoming(config)?;
+        return Ok(vec![render_template(
+            config, template, changesets, None, &details,
+        )?]);
+    }
+    let mut content = vec![format!("{}\n", UNRELEASED_HEADING)];
+    content.extend(render_sections(config, changesets));
+    Ok(content)
+}
+
+/// Remove all the current changesets after the release of `version`, including
+/// the pre-release state. With `archive_changesets`, the changesets are moved
+/// to the archive of the version instead of being deleted
+pub fn consume_changesets(config: &Config, version: &str) -> Result<(), VersionWiseError> {
+    let folder_path = &config.changeset_dir;
+    // Verify if the folder exist. It should only reach to this function in case that
+    // the changesets folder exists
+    let entries = fs::read_dir(folder_path).map_err(|e| VersionWiseError::io(folder_path, e))?;
+    let archive = config.archive_path(version);
+    if config.archive_changesets {
+        fs::create_dir_all(&archive).map_err(|e| VersionWiseError::io(&archive, e))?;
+    }
+    // Iterate over all the changesets in that folder
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // For security, verify if the entry is a file. The archive is a folder, so it's kept
+        if !path.is_file() {
+            continue;
         }
-    } else {
-        // In this case, panic. It should only reach to this function in case that
-        // the folder `.changeset` exists
-        panic!("The folder {} does not exist.", folder_path);
+        let file_name = entry.file_name();
+        let is_changeset =
+            path.extension().is_some_and(|ext| ext == "toml") && file_name != PRE_STATE_FILE;
+        // Try to archive or remove the file
+        let result = match config.archive_changesets && is_changeset {
+            true => fs::rename(&path, Path::new(&archive).join(&file_name)),
+            false => fs::remove_file(&path),
+        };
+        result.map_err(|e| VersionWiseError::io(path.display().to_string(), e))?;
     }
+    Ok(())
 }
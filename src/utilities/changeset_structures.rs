@@ -1,12 +1,12 @@
 /// Define some structures for changeset data, so it's easier for us
 /// to deserialize and work with changesets.
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// Represents a raw changeset with detailed information.
 /// Such as the changeset information and the detail. This is a
 /// raw representation of a changeset, before creating the normal
 /// structure.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct RawChangeset {
     pub changeset: ChangesetInfo,
     pub changes: ChangeDetails,
@@ -14,7 +14,7 @@ pub struct RawChangeset {
 
 /// Represents the information of a changeset. Which change_type
 /// does include, the module, and the version.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ChangesetInfo {
     pub change_type: String,
     pub tag: String,
@@ -23,7 +23,7 @@ pub struct ChangesetInfo {
 
 /// Represents the details of a changeset. Which modules are affected
 /// and the description of the changes.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ChangeDetails {
     pub modules: Vec<String>,
     pub description: String,
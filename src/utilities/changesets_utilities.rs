@@ -1,39 +1,32 @@
 // ================================ ///
 //      UTILITIES :: Changeset      ///
 // ================================ ///
+use std::cmp::Ordering;
 use std::fs;
-use std::io;
 use std::path::Path;
 // Local imports
 use crate::options::Changeset;
 use crate::utilities::changeset_structures::RawChangeset;
+use crate::utilities::config::ARCHIVE_DIR;
+use crate::utilities::pre_release::PRE_STATE_FILE;
+use crate::utilities::{Config, VersionWiseError};
 
 /// From a file content, process it and return the Changeset structure
-fn parse_changeset(file_name: &str) -> Option<Changeset> {
+fn parse_changeset(dir: &str, file_name: &str) -> Result<Changeset, VersionWiseError> {
     // We try to read the file at first
-    let file_path = format!(".changesets/{}", file_name);
-    let file_content = match fs::read_to_string(&file_path) {
-        Ok(content) => content,
-        Err(e) => {
-            println!("Error reading file: {}", e);
-            return None;
-        }
-    };
-    // First try to parse as TOML and provide clear error message if it fails
-    let raw_changeset: RawChangeset = match toml::from_str(&file_content) {
-        Ok(changeset) => changeset,
-        Err(e) => {
-            println!("Error reading TOML: {}", e);
-            // Print the first few lines of the file for debugging
-            return None;
-        }
-    };
+    let file_path = format!("{}/{}", dir, file_name);
+    let file_content =
+        fs::read_to_string(&file_path).map_err(|e| VersionWiseError::io(&file_path, e))?;
+    // Then try to parse it as TOML, pointing to the file if it fails
+    let raw_changeset: RawChangeset =
+        toml::from_str(&file_content).map_err(|e| VersionWiseError::parse(&file_path, e))?;
     // Then, we process the modules
     let modules = raw_changeset.changes.modules.join(", ");
 
     // And, at the end, we create the Changeset structure! Easy peasy!
-    Some(Changeset::new(
-        file_name.to_string(),
+    // The name of the changeset is the file name without the extension
+    Ok(Changeset::new(
+        file_name.trim_end_matches(".toml").to_string(),
         raw_changeset.changeset.change_type,
         modules,
         raw_changeset.changeset.tag,
@@ -42,49 +35,72 @@ fn parse_changeset(file_name: &str) -> Option<Changeset> {
     ))
 }
 
-/// Process a file from a file path and from there, process and get the changesets
-fn process_file(file_name: &str) -> io::Result<Changeset> {
-    // Let's see if we can create a changeset from here
-    if let Some(changeset) = parse_changeset(file_name) {
-        Ok(changeset)
-    } else {
-        Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "Invalid file format",
-        ))
-    }
-}
-
-pub fn get_current_changesets() -> Vec<Changeset> {
+/// Read the changesets of a folder, sorted
+fn read_changesets(dir: &str) -> Result<Vec<Changeset>, VersionWiseError> {
     // Initialize the changesets
     let mut changesets = vec![];
     // Get the directory where we can find the changesets
-    let changeset_dir = Path::new(".changesets");
+    let changeset_dir = Path::new(dir);
 
     // Iterate over all the entries in there
     if let Ok(entries) = fs::read_dir(changeset_dir) {
         // Check every entry on the entries
         for entry in entries {
             // Get the file
-            let dir_entry = entry.unwrap();
+            let dir_entry = entry.map_err(|e| VersionWiseError::io(dir, e))?;
             // Get the file path and file name
             let file_name = dir_entry.file_name();
             let file_path = &dir_entry.path();
-            // Process. if the filepath is a file and it's extension is .toml, then process
-            if file_path.is_file() && file_path.extension().is_some_and(|ext| ext == "toml") {
-                match process_file(file_name.to_str().unwrap()) {
-                    Ok(changeset) => {
-                        changesets.push(changeset);
-                    }
-                    Err(err) => {
-                        println!("Error processing file {:?}: {}", &file_path, err);
-                    }
-                }
+            // Process. if the filepath is a file and it's extension is .toml, then process.
+            // The pre-release state is not a changeset, so skip it. The archive of the
+            // released changesets is a folder, so it's skipped too
+            if file_path.is_file()
+                && file_path.extension().is_some_and(|ext| ext == "toml")
+                && file_name != PRE_STATE_FILE
+            {
+                changesets.push(parse_changeset(dir, &file_name.to_string_lossy())?);
             }
         }
     }
     // Sort them
     changesets.sort();
     // At the end, return the changesets
-    changesets
+    Ok(changesets)
+}
+
+pub fn get_current_changesets(config: &Config) -> Result<Vec<Changeset>, VersionWiseError> {
+    read_changesets(&config.changeset_dir)
+}
+
+/// Get the archived changesets of every release, as pairs of version and
+/// changesets. The releases are sorted from the newest one
+pub fn get_archived_changesets(
+    config: &Config,
+) -> Result<Vec<(String, Vec<Changeset>)>, VersionWiseError> {
+    let archive = config.changeset_path(ARCHIVE_DIR);
+    let mut releases = Vec::new();
+    // Without an archive, there are no releases
+    let Ok(entries) = fs::read_dir(&archive) else {
+        return Ok(releases);
+    };
+    for entry in entries {
+        let dir_entry = entry.map_err(|e| VersionWiseError::io(&archive, e))?;
+        if dir_entry.path().is_dir() {
+            let version = dir_entry.file_name().to_string_lossy().to_string();
+            let changesets = read_changesets(&config.archive_path(&version))?;
+            releases.push((version, changesets));
+        }
+    }
+    // Sort them with the version scheme, failing on the folders that are not versions
+    let scheme = &config.version_scheme;
+    for (version, _) in &releases {
+        scheme.normalize(version).map_err(|e| {
+            VersionWiseError::Version(format!(
+                "The archive `{}/{}` is not a release: {}",
+                archive, version, e
+            ))
+        })?;
+    }
+    releases.sort_by(|(a, _), (b, _)| scheme.compare(b, a).unwrap_or(Ordering::Equal));
+    Ok(releases)
 }
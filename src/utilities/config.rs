@@ -0,0 +1,829 @@
+/// ================================ ///
+///        UTILITIES :: Config       ///
+/// ================================ ///
+/// Discover and parse the VersionWise configuration. It is searched, in order, in:
+/// 1. The file given with `--config`.
+/// 2. `versionwise.toml` or `.versionwise.toml`, where the whole file is the configuration.
+/// 3. The `[tool.versionwise]` table of `pyproject.toml`.
+/// 4. The `[package.metadata.versionwise]` table of `Cargo.toml`.
+/// 5. The `"versionwise"` key of `package.json`.
+///
+/// The table is deserialized into a `RawConfig` and then validated, so every
+/// error points at the offending key (e.g. `tool.versionwise.tags[2].bump`).
+use glob::Pattern;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use tera::Tera;
+use toml::Value;
+// Local imports
+use crate::options::Changeset;
+use crate::utilities::calver::DEFAULT_CALVER_FORMAT;
+use crate::utilities::changelog_template::load_template;
+use crate::utilities::git_utils::get_origin_url;
+use crate::utilities::version_files::{RawVersionPath, VersionPath};
+use crate::utilities::version_operations::ChangeType;
+use crate::utilities::{VersionScheme, VersionWiseError};
+
+/// Files where the configuration is searched, in order of precedence
+const CONFIG_FILES: [&str; 5] = [
+    "versionwise.toml",
+    ".versionwise.toml",
+    "pyproject.toml",
+    "Cargo.toml",
+    "package.json",
+];
+
+/// Subfolder of the changesets folder where the released changesets are archived
+pub const ARCHIVE_DIR: &str = "archive";
+
+/// AI providers that can generate the changeset messages
+const AI_PROVIDERS: [&str; 2] = ["openai", "gemini"];
+
+/// Tags used when the configuration doesn't have any: (emoji, name, bump, description, template)
+const DEFAULT_TAGS: [(&str, &str, ChangeType, &str, &str); 14] = [
+    (
+        "⚰️",
+        "Remove",
+        ChangeType::Major,
+        "Removed features.",
+        "Remove ... functionality because ...",
+    ),
+    (
+        "🚚",
+        "Rename",
+        ChangeType::Major,
+        "Renamed features.",
+        "Rename ... to ... to better reflect ...",
+    ),
+    (
+        "✏️",
+        "I/O",
+        ChangeType::Major,
+        "Changing input/output of features.",
+        "Change ... input/output to ...",
+    ),
+    (
+        "💥",
+        "Behavior",
+        ChangeType::Major,
+        "Changing features behavior.",
+        "Change behavior of ... to ...",
+    ),
+    (
+        "✨",
+        "Feature",
+        ChangeType::Minor,
+        "New feature.",
+        "Add ... feature that allows ...",
+    ),
+    (
+        "➕",
+        "Add",
+        ChangeType::Minor,
+        "Add functionality to existing feature.",
+        "Add ... functionality to ...",
+    ),
+    (
+        "✏️",
+        "I/O",
+        ChangeType::Minor,
+        "Include optional input/output to a feature.",
+        "Include optional ... parameter to ...",
+    ),
+    (
+        "🗑️",
+        "Deprecated",
+        ChangeType::Minor,
+        "Deprecated features.",
+        "Mark ... as deprecated, to be removed in version ...",
+    ),
+    (
+        "♻️",
+        "Refactor",
+        ChangeType::Patch,
+        "Refactor of existing code.",
+        "Refactor ... to improve ...",
+    ),
+    (
+        "🐛",
+        "Bug",
+        ChangeType::Patch,
+        "Fix a bug.",
+        "Fix ... bug where ...",
+    ),
+    (
+        "⚡️",
+        "Optimization",
+        ChangeType::Patch,
+        "Simple optimization of code.",
+        "Optimize ... to improve performance by ...",
+    ),
+    (
+        "🧪",
+        "Tests",
+        ChangeType::Patch,
+        "Include or update tests.",
+        "Add tests for ... to verify ...",
+    ),
+    (
+        "🩹",
+        "Patch",
+        ChangeType::Patch,
+        "Include or delete logs, catch errors or related things.",
+        "Update ... to handle ...",
+    ),
+    (
+        "🔧",
+        "Internal",
+        ChangeType::None,
+        "CI, docs and other changes that don't need a release.",
+        "Update ... to ...",
+    ),
+];
+
+/// The configuration, as it's written in the configuration file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    version_path: Option<Vec<RawVersionPath>>,
+    version_scheme: Option<String>,
+    calver_format: Option<String>,
+    changeset_dir: Option<String>,
+    changelog: Option<String>,
+    base_ref: Option<String>,
+    #[serde(default)]
+    ignore: Vec<String>,
+    tags: Option<Vec<TagConfig>>,
+    #[serde(default)]
+    ai: AiSettings,
+    #[serde(default)]
+    no_bump: NoBumpSettings,
+    #[serde(default)]
+    changelog_format: ChangelogFormat,
+    #[serde(default)]
+    unreleased: bool,
+    #[serde(default)]
+    compare_links: bool,
+    repository: Option<String>,
+    tag_prefix: Option<String>,
+    changelog_template: Option<String>,
+    #[serde(default)]
+    changelog_outputs: Vec<ChangelogOutput>,
+    #[serde(default)]
+    archive_changesets: bool,
+}
+
+/// A tag that can be given to the changesets of a change type
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TagConfig {
+    pub name: String,
+    /// Change type of the changesets with this tag
+    pub bump: ChangeType,
+    #[serde(default)]
+    pub emoji: String,
+    #[serde(default)]
+    pub description: String,
+    /// Message suggested when the changeset is written from a template
+    #[serde(default)]
+    pub template: String,
+    /// Heading of its CHANGELOG section. By default, the name of the tag
+    pub section: Option<String>,
+}
+
+impl TagConfig {
+    /// Text shown when the tag is selected, like `🐛 Bug: Fix a bug.`
+    pub fn label(&self) -> String {
+        let mut label = match self.emoji.is_empty() {
+            true => self.name.clone(),
+            false => format!("{} {}", self.emoji, self.name),
+        };
+        if !self.description.is_empty() {
+            label.push_str(&format!(": {}", self.description));
+        }
+        label
+    }
+
+    /// Heading of the CHANGELOG section where the changesets of this tag go
+    pub fn heading(&self) -> &str {
+        self.section.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Settings of the AI message generator. The missing ones are taken from the
+/// `AI_PROVIDER` and `MODEL` environment variables
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AiSettings {
+    /// `openai` or `gemini`
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    /// Environment variable with the API key. By default, `API_KEY`
+    pub api_key_env: Option<String>,
+}
+
+/// Format of the CHANGELOG entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangelogFormat {
+    /// `## [X.Y.Z]`, with a section for each tag
+    #[default]
+    VersionWise,
+    /// Keep a Changelog (https://keepachangelog.com): `## [X.Y.Z] - YYYY-MM-DD`,
+    /// with the Added, Changed, Deprecated, Removed, Fixed and Security sections
+    KeepAChangelog,
+}
+
+/// Format of an additional CHANGELOG file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Markdown,
+    /// reStructuredText, for Sphinx
+    #[serde(alias = "restructuredtext")]
+    Rst,
+    #[serde(alias = "adoc")]
+    AsciiDoc,
+    Html,
+    Json,
+}
+
+/// An additional CHANGELOG file, where `bump` also writes every release
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChangelogOutput {
+    pub path: String,
+    pub format: OutputFormat,
+}
+
+/// What `bump` does when every pending changeset has the `none` change type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoBumpRelease {
+    /// Don't release anything. The changesets wait for the next release
+    #[default]
+    Skip,
+    /// Write them in the CHANGELOG under the current version
+    Current,
+}
+
+/// Settings of the changesets that don't bump the version
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NoBumpSettings {
+    #[serde(default)]
+    pub release: NoBumpRelease,
+    /// Whether they're written in the CHANGELOG
+    #[serde(default = "enabled")]
+    pub changelog: bool,
+}
+
+impl Default for NoBumpSettings {
+    fn default() -> Self {
+        NoBumpSettings {
+            release: NoBumpRelease::default(),
+            changelog: true,
+        }
+    }
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// Configuration of VersionWise
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Files where the version is written. The first one is the source of truth
+    pub version_paths: Vec<VersionPath>,
+    /// Scheme used to parse and bump the versions
+    pub version_scheme: VersionScheme,
+    /// Folder where the changesets are stored
+    pub changeset_dir: String,
+    /// Whether the released changesets are archived instead of deleted
+    pub archive_changesets: bool,
+    /// Path of the CHANGELOG file
+    pub changelog: String,
+    /// Format of the CHANGELOG entries
+    pub changelog_format: ChangelogFormat,
+    /// User-defined template of the CHANGELOG entries
+    pub changelog_template: Option<Tera>,
+    /// Additional CHANGELOG files, in other formats
+    pub changelog_outputs: Vec<ChangelogOutput>,
+    /// Whether `create` keeps an `Unreleased` section with the pending changesets
+    pub unreleased: bool,
+    /// Whether the CHANGELOG has a footer with the compare links of the releases
+    pub compare_links: bool,
+    /// Web URL of the repository. By default, the one of the `origin` remote
+    pub repository: Option<String>,
+    /// Prefix of the git tags of the releases (`v` for `v1.2.0`)
+    pub tag_prefix: String,
+    /// Tags available for the changesets
+    pub tags: Vec<TagConfig>,
+    /// Settings of the AI message generator
+    pub ai: AiSettings,
+    /// Settings of the changesets that don't bump the version
+    pub no_bump: NoBumpSettings,
+    /// Git reference that `check` compares against
+    pub base_ref: Option<String>,
+    /// Glob patterns of the files that `check` doesn't take into account
+    pub ignore: Vec<Pattern>,
+}
+
+/// Read the VersionWise table of a configuration file.
+/// Returns `None` if the file doesn't have a VersionWise configuration.
+fn read_config_file(path: &str) -> Result<Option<Value>, VersionWiseError> {
+    let content = fs::read_to_string(path).map_err(|e| VersionWiseError::io(path, e))?;
+    // JSON files, like `package.json`, use the `"versionwise"` key
+    if file_name(path).ends_with(".json") {
+        let json: serde_json::Value =
+            serde_json::from_str(&content).map_err(|e| VersionWiseError::parse(path, e))?;
+        return match json.get("versionwise") {
+            Some(versionwise) => Value::try_from(versionwise)
+                .map(Some)
+                .map_err(|e| VersionWiseError::parse(path, e)),
+            None => Ok(None),
+        };
+    }
+    // And the TOML files depend on their name
+    let toml_config: Value = content
+        .parse()
+        .map_err(|e| VersionWiseError::parse(path, e))?;
+    let table = match file_name(path) {
+        "pyproject.toml" => toml_config.get("tool").and_then(|t| t.get("versionwise")),
+        "Cargo.toml" => toml_config
+            .get("package")
+            .and_then(|p| p.get("metadata"))
+            .and_then(|m| m.get("versionwise")),
+        _ => Some(&toml_config),
+    };
+    Ok(table.cloned())
+}
+
+/// Find the first file of `dir` with a VersionWise configuration,
+/// following the order of `CONFIG_FILES`
+fn find_config(dir: &Path) -> Result<Option<(String, Value)>, VersionWiseError> {
+    for file in CONFIG_FILES {
+        let path = dir.join(file).to_string_lossy().to_string();
+        if !Path::new(&path).is_file() {
+            continue;
+        }
+        if let Some(table) = read_config_file(&path)? {
+            return Ok(Some((path, table)));
+        }
+    }
+    Ok(None)
+}
+
+fn file_name(path: &str) -> &str {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+}
+
+/// Whether the version is also written in the file that holds the configuration
+fn is_manifest(path: &str) -> bool {
+    matches!(
+        file_name(path),
+        "pyproject.toml" | "Cargo.toml" | "package.json"
+    )
+}
+
+/// Key of the VersionWise table inside of the configuration file
+fn table_key(path: &str) -> &'static str {
+    match file_name(path) {
+        "pyproject.toml" => "tool.versionwise",
+        "Cargo.toml" => "package.metadata.versionwise",
+        name if name.ends_with(".json") => "versionwise",
+        _ => "",
+    }
+}
+
+/// Error of an invalid key of the configuration found in `path`
+fn invalid_key(path: &str, key: &str, reason: impl std::fmt::Display) -> VersionWiseError {
+    let full_key = match (table_key(path), key) {
+        (table, "" | ".") => table.to_string(),
+        ("", key) => key.to_string(),
+        (table, key) => format!("{}.{}", table, key),
+    };
+    match full_key.is_empty() {
+        true => {
+            VersionWiseError::Config(format!("Invalid configuration in `{}`: {}", path, reason))
+        }
+        false => {
+            VersionWiseError::Config(format!("Invalid `{}` in `{}`: {}", full_key, path, reason))
+        }
+    }
+}
+
+/// Point a configuration error at the key that caused it
+fn with_key(path: &str, key: &str, error: VersionWiseError) -> VersionWiseError {
+    match error {
+        VersionWiseError::Config(message) => invalid_key(path, key, message),
+        error => error,
+    }
+}
+
+/// Normalize a folder path, so it can be compared with the paths given by git
+fn clean_dir(dir: &str) -> String {
+    dir.trim_start_matches("./")
+        .trim_end_matches('/')
+        .to_string()
+}
+
+impl Config {
+    /// Load the configuration, from the given file or from the first file
+    /// found in the current directory
+    pub fn load(path: Option<&String>) -> Result<Config, VersionWiseError> {
+        if let Some(path) = path {
+            return match read_config_file(path)? {
+                Some(table) => Config::from_table(path, table),
+                None => Err(VersionWiseError::Config(format!(
+                    "The file `{}` doesn't have a VersionWise configuration.",
+                    path
+                ))),
+            };
+        }
+        match find_config(Path::new(""))? {
+            Some((path, table)) => Config::from_table(&path, table),
+            None => Err(VersionWiseError::Config(
+            "Couldn't find the VersionWise configuration. Add a `versionwise.toml`, a [tool.versionwise] table in the `pyproject.toml`, a [package.metadata.versionwise] table in the `Cargo.toml` or a \"versionwise\" key in the `package.json`.".to_string(),
+            )),
+        }
+    }
+
+    /// Parse and validate the configuration table found in `path`
+    fn from_table(path: &str, table: Value) -> Result<Config, VersionWiseError> {
+        let raw: RawConfig = serde_path_to_error::deserialize(table)
+            .map_err(|e| invalid_key(path, &e.path().to_string(), e.inner().message()))?;
+        // Get the version paths. The manifests hold their own version by default
+        let version_paths = match raw.version_path {
+            Some(paths) => paths
+                .into_iter()
+                .enumerate()
+                .map(|(index, raw_path)| {
+                    VersionPath::from_raw(raw_path)
+                        .map_err(|e| invalid_key(path, &format!("version_path[{}]", index), e))
+                })
+                .collect::<Result<Vec<VersionPath>, VersionWiseError>>()?,
+            None if is_manifest(path) => vec![VersionPath::new(path)],
+            None => return Err(invalid_key(path, "version_path", "the field is required")),
+        };
+        if version_paths.is_empty() {
+            return Err(invalid_key(
+                path,
+                "version_path",
+                "it needs at least one version file",
+            ));
+        }
+        // The version scheme. The CalVer format is only used by its scheme
+        let version_scheme = match raw.version_scheme {
+            None => VersionScheme::SemVer,
+            Some(name) => {
+                VersionScheme::from_name(&name).map_err(|e| with_key(path, "version_scheme", e))?
+            }
+        };
+        let version_scheme = match version_scheme {
+            VersionScheme::CalVer(_) => VersionScheme::CalVer(
+                raw.calver_format
+                    .as_deref()
+                    .unwrap_or(DEFAULT_CALVER_FORMAT)
+                    .parse()
+                    .map_err(|e| with_key(path, "calver_format", e))?,
+            ),
+            scheme => scheme,
+        };
+        // The folders and files of VersionWise
+        let changeset_dir = clean_dir(raw.changeset_dir.as_deref().unwrap_or(".changesets"));
+        if changeset_dir.is_empty() {
+            return Err(invalid_key(path, "changeset_dir", "the path is empty"));
+        }
+        let changelog = raw.changelog.unwrap_or("CHANGELOG.md".to_string());
+        if changelog.trim().is_empty() {
+            return Err(invalid_key(path, "changelog", "the path is empty"));
+        }
+        // The tags of the changesets
+        let tags = match raw.tags {
+            None => DEFAULT_TAGS
+                .iter()
+                .map(|(emoji, name, bump, description, template)| TagConfig {
+                    name: name.to_string(),
+                    bump: *bump,
+                    emoji: emoji.to_string(),
+                    description: description.to_string(),
+                    template: template.to_string(),
+                    section: None,
+                })
+                .collect(),
+            Some(tags) => tags,
+        };
+        for (index, tag) in tags.iter().enumerate() {
+            if tag.name.trim().is_empty() {
+                return Err(invalid_key(
+                    path,
+                    &format!("tags[{}].name", index),
+                    "the name is empty",
+                ));
+            }
+            if tags[..index]
+                .iter()
+                .any(|other| other.name == tag.name && other.bump == tag.bump)
+            {
+                return Err(invalid_key(
+                    path,
+                    &format!("tags[{}].name", index),
+                    format!(
+                        "`{}` is repeated for the `{}` change type",
+                        tag.name, tag.bump
+                    ),
+                ));
+            }
+        }
+        // The AI provider, if it's configured
+        if let Some(provider) = &raw.ai.provider {
+            if !AI_PROVIDERS.contains(&provider.to_lowercase().as_str()) {
+                return Err(invalid_key(
+                    path,
+                    "ai.provider",
+                    format!(
+                        "`{}` should be one of {}",
+                        provider,
+                        AI_PROVIDERS.join(", ")
+                    ),
+                ));
+            }
+        }
+        // The template of the CHANGELOG entries
+        let changelog_template = match &raw.changelog_template {
+            Some(template) => Some(
+                load_template(template).map_err(|e| invalid_key(path, "changelog_template", e))?,
+            ),
+            None => None,
+        };
+        // The additional CHANGELOG files can't overwrite each other
+        for (index, output) in raw.changelog_outputs.iter().enumerate() {
+            let key = format!("changelog_outputs[{}].path", index);
+            if output.path.trim().is_empty() {
+                return Err(invalid_key(path, &key, "the path is empty"));
+            }
+            if output.path == changelog
+                || raw.changelog_outputs[..index]
+                    .iter()
+                    .any(|other| other.path == output.path)
+            {
+                return Err(invalid_key(
+                    path,
+                    &key,
+                    format!("`{}` is already a CHANGELOG file", output.path),
+                ));
+            }
+        }
+        // The repository of the compare links. It's read from the `origin`
+        // remote now, so no command fails after writing its files
+        let repository = match raw.repository {
+            Some(repository) => {
+                if !repository.starts_with("https://") && !repository.starts_with("http://") {
+                    return Err(invalid_key(
+                        path,
+                        "repository",
+                        format!("`{}` should be an http(s) URL", repository),
+                    ));
+                }
+                Some(repository.trim_end_matches('/').to_string())
+            }
+            None if raw.compare_links => Some(get_origin_url().map_err(|e| {
+                invalid_key(
+                    path,
+                    "repository",
+                    format!(
+                        "`compare_links` needs it, and it couldn't be read from the `origin` remote ({})",
+                        e
+                    ),
+                )
+            })?),
+            None => None,
+        };
+        // The settings of `check`
+        let ignore = raw
+            .ignore
+            .iter()
+            .enumerate()
+            .map(|(index, glob)| {
+                Pattern::new(glob).map_err(|e| {
+                    invalid_key(
+                        path,
+                        &format!("ignore[{}]", index),
+                        format!("`{}` is not a valid glob pattern ({})", glob, e),
+                    )
+                })
+            })
+            .collect::<Result<Vec<Pattern>, VersionWiseError>>()?;
+        Ok(Config {
+            version_paths,
+            version_scheme,
+            changeset_dir,
+            archive_changesets: raw.archive_changesets,
+            changelog,
+            changelog_format: raw.changelog_format,
+            changelog_template,
+            changelog_outputs: raw.changelog_outputs,
+            unreleased: raw.unreleased,
+            compare_links: raw.compare_links,
+            repository,
+            tag_prefix: raw.tag_prefix.unwrap_or("v".to_string()),
+            tags,
+            ai: raw.ai,
+            no_bump: raw.no_bump,
+            base_ref: raw.base_ref,
+            ignore,
+        })
+    }
+
+    /// Path of a file inside of the changesets folder
+    pub fn changeset_path(&self, file: &str) -> String {
+        format!("{}/{}", self.changeset_dir, file)
+    }
+
+    /// Folder where the changesets of a release are archived
+    pub fn archive_path(&self, version: &str) -> String {
+        self.changeset_path(&format!("{}/{}", ARCHIVE_DIR, version))
+    }
+
+    /// Tags that can be used for a change type
+    pub fn tags_for(&self, change: ChangeType) -> Vec<&TagConfig> {
+        self.tags.iter().filter(|tag| tag.bump == change).collect()
+    }
+
+    /// CHANGELOG section of a changeset. The tags that are not configured
+    /// (e.g. removed after the changeset was written) use their own name
+    pub fn section_of(&self, changeset: &Changeset) -> String {
+        let change = changeset.change.parse::<ChangeType>().ok();
+        self.tags
+            .iter()
+            .filter(|tag| tag.name == changeset.tag)
+            .min_by_key(|tag| Some(tag.bump) != change)
+            .map_or(changeset.tag.clone(), |tag| tag.heading().to_string())
+    }
+
+    /// Whether a changeset is written in the CHANGELOG
+    pub fn in_changelog(&self, changeset: &Changeset) -> bool {
+        self.no_bump.changelog || !changeset.is_no_bump()
+    }
+
+    /// Position of a CHANGELOG section, following the order of the tags
+    pub fn section_position(&self, section: &str) -> usize {
+        self.tags
+            .iter()
+            .position(|tag| tag.heading() == section)
+            .unwrap_or(self.tags.len())
+    }
+}
+
+#[cfg(test)]
+impl Config {
+    /// Configuration of a `versionwise.toml` with the given content
+    pub fn from_toml(content: &str) -> Config {
+        Config::from_table("versionwise.toml", content.parse().unwrap()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse the configuration of `file`, written with the given content
+    fn parse(file: &str, content: &str) -> Result<Config, VersionWiseError> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(file);
+        fs::write(&path, content).unwrap();
+        let table = read_config_file(&path.to_string_lossy())?.expect("no configuration");
+        Config::from_table(file, table)
+    }
+
+    #[test]
+    fn reads_every_kind_of_file() {
+        let cases = [
+            (
+                "versionwise.toml",
+                "version_path = [\"setup.py\"]",
+                "setup.py",
+            ),
+            (
+                "pyproject.toml",
+                "[project]\nversion = \"1.0.0\"\n\n[tool.versionwise]\nunreleased = true",
+                "pyproject.toml",
+            ),
+            (
+                "Cargo.toml",
+                "[package]\nversion = \"1.0.0\"\n\n[package.metadata.versionwise]",
+                "Cargo.toml",
+            ),
+            (
+                "package.json",
+                r#"{"version": "1.0.0", "versionwise": {"changeset_dir": "./sets/"}}"#,
+                "package.json",
+            ),
+        ];
+        for (file, content, version_path) in cases {
+            let config = parse(file, content).unwrap_or_else(|e| panic!("{}: {}", file, e));
+            assert_eq!(config.version_paths[0].path, version_path, "{}", file);
+        }
+        let config = parse("package.json", cases[3].1).unwrap();
+        assert_eq!(config.changeset_dir, "sets");
+    }
+
+    #[test]
+    fn errors_point_at_the_invalid_key() {
+        let tags = "name = \"Bug\"\nbump = \"patch\"\n\n[[tool.versionwise.tags]]";
+        let cases = [
+            (
+                "pyproject.toml",
+                format!(
+                    "[[tool.versionwise.tags]]\n{}\nname = \"Big\"\nbump = \"huge\"",
+                    tags
+                ),
+                "`tool.versionwise.tags[1].bump`",
+            ),
+            (
+                "pyproject.toml",
+                format!(
+                    "[[tool.versionwise.tags]]\n{}\nname = \"Bug\"\nbumps = \"patch\"",
+                    tags
+                ),
+                "`tool.versionwise.tags[1].bumps`",
+            ),
+            (
+                "pyproject.toml",
+                format!(
+                    "[[tool.versionwise.tags]]\n{}\nname = \"Bug\"\nbump = \"patch\"",
+                    tags
+                ),
+                "`tool.versionwise.tags[1].name`",
+            ),
+            (
+                "versionwise.toml",
+                "version_path = [\"a.py\"]\n[ai]\nprovider = \"claude\"".to_string(),
+                "`ai.provider`",
+            ),
+            (
+                "package.json",
+                r#"{"versionwise": {"ai": {"provider": "claude"}}}"#.to_string(),
+                "`versionwise.ai.provider`",
+            ),
+            (
+                "versionwise.toml",
+                "version_path = []".to_string(),
+                "`version_path`",
+            ),
+            (
+                "versionwise.toml",
+                "changeset_dir = \"./sets\"".to_string(),
+                "`version_path`",
+            ),
+            (
+                "Cargo.toml",
+                "[package.metadata.versionwise]\nchangeset_dir = \"./\"".to_string(),
+                "`package.metadata.versionwise.changeset_dir`",
+            ),
+            (
+                "package.json",
+                r#"{"versionwise": {"version_path": [{"path": "a.py", "key": "v"}]}}"#.to_string(),
+                "`versionwise.version_path[0]`",
+            ),
+            (
+                "versionwise.toml",
+                "version_path = [\"a.py\"]\nunknown = 1".to_string(),
+                "Invalid `unknown` in `versionwise.toml`",
+            ),
+        ];
+        for (file, content, key) in cases {
+            let error = parse(file, &content).unwrap_err();
+            assert!(matches!(error, VersionWiseError::Config(_)), "{}", content);
+            assert!(error.to_string().contains(key), "{}: {}", key, error);
+        }
+    }
+
+    #[test]
+    fn finds_the_files_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |file: &str, content: &str| fs::write(dir.path().join(file), content).unwrap();
+        let found = || {
+            find_config(dir.path())
+                .unwrap()
+                .map(|(path, _)| file_name(&path).to_string())
+        };
+        assert_eq!(found(), None);
+        // The manifests without a VersionWise table are skipped
+        write("package.json", r#"{"versionwise": {}}"#);
+        write("Cargo.toml", "[package]\nversion = \"1.0.0\"");
+        assert_eq!(found().as_deref(), Some("package.json"));
+        write("pyproject.toml", "[tool.versionwise]");
+        assert_eq!(found().as_deref(), Some("pyproject.toml"));
+        write(".versionwise.toml", "");
+        assert_eq!(found().as_deref(), Some(".versionwise.toml"));
+        write("versionwise.toml", "");
+        assert_eq!(found().as_deref(), Some("versionwise.toml"));
+    }
+}
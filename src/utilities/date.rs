@@ -0,0 +1,74 @@
+/// ================================ ///
+///         UTILITIES :: Date        ///
+/// ================================ ///
+/// Minimal calendar date, used for the calendar versions.
+use std::env;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+// Local imports
+use crate::utilities::VersionWiseError;
+
+/// Environment variable used to fix the current date, following the
+/// reproducible builds convention (https://reproducible-builds.org/specs/source-date-epoch/)
+const SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
+
+/// A date of the proleptic Gregorian calendar, in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: u64,
+    pub month: u64,
+    pub day: u64,
+}
+
+impl Date {
+    pub fn new(year: u64, month: u64, day: u64) -> Date {
+        Date { year, month, day }
+    }
+
+    /// Get the date of a Unix timestamp, in seconds
+    pub fn from_unix(seconds: u64) -> Date {
+        // Convert the days since 1970-01-01 to a civil date. Algorithm from
+        // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let days = seconds / 86_400 + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + u64::from(month <= 2);
+        Date::new(year, month, day)
+    }
+
+    /// Get the current date. It can be fixed with the `SOURCE_DATE_EPOCH`
+    /// environment variable, a Unix timestamp
+    pub fn today() -> Result<Date, VersionWiseError> {
+        if let Ok(value) = env::var(SOURCE_DATE_EPOCH) {
+            let seconds = value.trim().parse().map_err(|_| {
+                VersionWiseError::Config(format!(
+                    "`{}` should be a Unix timestamp, but it is `{}`.",
+                    SOURCE_DATE_EPOCH, value
+                ))
+            })?;
+            return Ok(Date::from_unix(seconds));
+        }
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        Ok(Date::from_unix(seconds))
+    }
+}
+
+impl fmt::Display for Date {
+    /// Write the date in the ISO 8601 format (`2024-05-01`)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
@@ -0,0 +1,131 @@
+/// ================================ ///
+///        UTILITIES :: Errors       ///
+/// ================================ ///
+use std::fmt;
+use std::io;
+
+/// Every error that VersionWise can return to the user.
+///
+/// Each kind of error exits the process with a different code, so scripts
+/// can react to them:
+///
+/// | Code | Error                                         |
+/// |------|-----------------------------------------------|
+/// | 0    | Success                                       |
+/// | 1    | `Check`: a required changeset is missing      |
+/// | 2    | Usage error of the CLI, reported by clap      |
+/// | 3    | `Io`: a file could not be read or written     |
+/// | 4    | `Parse`: a file has an invalid format         |
+/// | 5    | `Version`: a version is missing or invalid    |
+/// | 6    | `Ai`: the AI message generation failed        |
+/// | 7    | `Input`: missing or invalid user input        |
+/// | 8    | `Git`: a git command failed                   |
+/// | 9    | `Config`: missing or invalid configuration    |
+#[derive(Debug)]
+pub enum VersionWiseError {
+    /// The configuration is missing or has invalid values
+    Config(String),
+    /// A file or directory could not be read or written
+    Io { path: String, source: io::Error },
+    /// A file could not be parsed
+    Parse { path: String, message: String },
+    /// A version could not be found, parsed or calculated
+    Version(String),
+    /// The AI provider is not configured or its call failed
+    Ai(String),
+    /// The user input (CLI flags or prompts) is missing or invalid
+    Input(String),
+    /// A git command could not be run or failed
+    Git(String),
+    /// The `check` command found changes without a changeset
+    Check(String),
+}
+
+impl VersionWiseError {
+    /// Build an `Io` error for the given path
+    pub fn io(path: impl Into<String>, source: io::Error) -> Self {
+        VersionWiseError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    /// Build a `Parse` error for the given path
+    pub fn parse(path: impl Into<String>, message: impl fmt::Display) -> Self {
+        VersionWiseError::Parse {
+            path: path.into(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Exit code used by the process when this error happens
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            VersionWiseError::Config(_) => 9,
+            VersionWiseError::Io { .. } => 3,
+            VersionWiseError::Parse { .. } => 4,
+            VersionWiseError::Version(_) => 5,
+            VersionWiseError::Ai(_) => 6,
+            VersionWiseError::Input(_) => 7,
+            VersionWiseError::Git(_) => 8,
+            VersionWiseError::Check(_) => 1,
+        }
+    }
+}
+
+impl fmt::Display for VersionWiseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionWiseError::Config(message) => write!(f, "Configuration error: {}", message),
+            VersionWiseError::Io { path, source } => write!(f, "`{}`: {}", path, source),
+            VersionWiseError::Parse { path, message } => {
+                write!(f, "Invalid format in `{}`: {}", path, message)
+            }
+            VersionWiseError::Version(message) => write!(f, "Version error: {}", message),
+            VersionWiseError::Ai(message) => write!(f, "AI error: {}", message),
+            VersionWiseError::Input(message) => write!(f, "{}", message),
+            VersionWiseError::Git(message) => write!(f, "Git error: {}", message),
+            VersionWiseError::Check(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for VersionWiseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VersionWiseError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<requestty::ErrorKind> for VersionWiseError {
+    fn from(error: requestty::ErrorKind) -> Self {
+        VersionWiseError::Input(format!("The question could not be answered: {}", error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_codes_are_distinct_from_clap() {
+        let errors = [
+            VersionWiseError::Check(String::new()),
+            VersionWiseError::io("", io::Error::other("")),
+            VersionWiseError::parse("", ""),
+            VersionWiseError::Version(String::new()),
+            VersionWiseError::Ai(String::new()),
+            VersionWiseError::Input(String::new()),
+            VersionWiseError::Git(String::new()),
+            VersionWiseError::Config(String::new()),
+        ];
+        let mut codes: Vec<i32> = errors.iter().map(|e| e.exit_code()).collect();
+        // clap exits with 2 on usage errors
+        assert!(!codes.contains(&2));
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+    }
+}
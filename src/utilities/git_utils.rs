@@ -0,0 +1,116 @@
+/// ================================ ///
+///         UTILITIES :: Git         ///
+/// ================================ ///
+use std::collections::BTreeSet;
+use std::process::Command;
+// Local imports
+use crate::utilities::VersionWiseError;
+
+/// Run a git command and return its output lines
+fn git_lines(args: &[&str]) -> Result<Vec<String>, VersionWiseError> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| VersionWiseError::Git(format!("Cannot run `git`: {}", e)))?;
+    // If git failed, show what it said
+    if !output.status.success() {
+        return Err(VersionWiseError::Git(format!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// Get changed files from git.
+///
+/// It includes the uncommitted changes against `HEAD` and the untracked files.
+/// If a `base` reference is given, it also includes every file changed in the
+/// current branch since it diverged from that reference (`base...HEAD`).
+pub fn get_git_changed_files(base: Option<&str>) -> Result<Vec<String>, VersionWiseError> {
+    // Use a set so the files are unique and sorted
+    let mut changed_files = BTreeSet::new();
+    // The changes of the branch, if there's a base to compare against
+    if let Some(base) = base {
+        let range = format!("{}...HEAD", base);
+        changed_files.extend(git_lines(&["diff", "--name-only", &range])?);
+    }
+    // The uncommitted changes
+    changed_files.extend(git_lines(&["diff", "--name-only", "HEAD"])?);
+    // And the new files that are not tracked yet
+    changed_files.extend(git_lines(&["ls-files", "--others", "--exclude-standard"])?);
+    Ok(changed_files.into_iter().collect())
+}
+
+/// Get the files added in the current branch since it diverged from `base`,
+/// including the uncommitted and untracked ones. The modified and renamed
+/// files are left out
+pub fn get_git_added_files(base: &str) -> Result<Vec<String>, VersionWiseError> {
+    let mut added_files = BTreeSet::new();
+    let range = format!("{}...HEAD", base);
+    added_files.extend(git_lines(&[
+        "diff",
+        "--name-only",
+        "--diff-filter=A",
+        &range,
+    ])?);
+    added_files.extend(git_lines(&[
+        "diff",
+        "--name-only",
+        "--diff-filter=A",
+        "HEAD",
+    ])?);
+    added_files.extend(git_lines(&["ls-files", "--others", "--exclude-standard"])?);
+    Ok(added_files.into_iter().collect())
+}
+
+/// Get the web URL of the `origin` remote, like `https://github.com/owner/repo`.
+/// The SSH remotes (`git@github.com:owner/repo.git`) are converted to HTTPS
+pub fn get_origin_url() -> Result<String, VersionWiseError> {
+    let remote = git_lines(&["remote", "get-url", "origin"])?
+        .into_iter()
+        .next()
+        .ok_or_else(|| VersionWiseError::Git("The `origin` remote has no URL.".to_string()))?;
+    let remote = remote.trim().trim_end_matches('/');
+    let remote = remote.strip_suffix(".git").unwrap_or(remote);
+    // `https://host/owner/repo`, `ssh://git@host/owner/repo` or `git@host:owner/repo`
+    let url = match remote.split_once("://") {
+        Some((_, rest)) => {
+            let rest = rest.rsplit_once('@').map_or(rest, |(_, rest)| rest);
+            format!("https://{}", rest)
+        }
+        None => {
+            let rest = remote.split_once('@').map_or(remote, |(_, rest)| rest);
+            format!("https://{}", rest.replacen(':', "/", 1))
+        }
+    };
+    Ok(url)
+}
+
+/// Get the authors of the commits that touched a file, in order of their first
+/// commit. The file is followed through renames, like the archived changesets.
+/// Outside of a git repository, or for new files, there are none
+pub fn get_file_authors(path: &str) -> Vec<String> {
+    let mut authors: Vec<String> = Vec::new();
+    let lines = git_lines(&["log", "--follow", "--format=%an", "--", path]).unwrap_or_default();
+    // The log goes from the newest commit
+    for author in lines.into_iter().rev() {
+        if !authors.contains(&author) {
+            authors.push(author);
+        }
+    }
+    authors
+}
+
+/// Get the date (`2024-05-01`) of the commit that added a path. Outside of a
+/// git repository, or for new paths, there's none
+pub fn get_added_date(path: &str) -> Option<String> {
+    git_lines(&["log", "--diff-filter=A", "--format=%cs", "--", path])
+        .ok()
+        .and_then(|lines| lines.last().cloned())
+}
@@ -1,13 +1,36 @@
 // Module declarations
 pub mod ai_calls;
 pub mod ai_message_generator;
+pub mod calver;
+pub mod changelog;
+pub mod changelog_links;
+pub mod changelog_outputs;
+pub mod changelog_template;
 pub mod changelog_utils;
 pub mod changeset_structures;
+pub mod config;
+pub mod date;
+pub mod errors;
+pub mod git_utils;
+pub mod pep440;
+pub mod pre_release;
+pub mod version;
+pub mod version_files;
 pub mod version_operations;
+pub mod version_scheme;
 
 // Re-exports
 pub use ai_message_generator::{generate_ai_message, AIConfig};
-pub use changelog_utils::{create_changelog, new_changelog_entry, open_changelog};
+pub use changelog_utils::{
+    consume_changesets, group_by_section, new_changelog_entry, open_changelog, rebuild_changelog,
+    render_changelog, unreleased_entry, update_unreleased, write_changelog,
+};
+pub use config::Config;
+pub use errors::VersionWiseError;
+pub use git_utils::{get_git_added_files, get_git_changed_files};
+pub use pre_release::{read_pre_state, remove_pre_state, write_pre_state, PreMode, PreState};
+pub use version::Version;
+pub use version_scheme::VersionScheme;
 
 /// Make the modules accessible
 mod changesets_utilities;
@@ -15,165 +38,228 @@ mod sets_utils;
 mod subcommands;
 // Local imports
 use crate::options::Changeset;
-pub use changesets_utilities::get_current_changesets;
+pub use changesets_utilities::{get_archived_changesets, get_current_changesets};
 pub use sets_utils::{create_changeset_folder, write_changeset_file};
 pub use subcommands::create_subcommands;
+use version_files::{locate_version, VersionPath};
+use version_operations::highest_change_type;
 // Libraries to use
-use regex::Regex;
 use std::fs;
-use std::io::{BufRead, BufReader, Read, Write};
-use toml::Value;
 
-pub fn find_version() -> String {
-    // Find the version in the current path
-    let version_paths = find_version_in_file();
-    // Using this, return the version
-    open_path(version_paths[0].clone())
+/// Read the current version, from the first version path
+pub fn find_version(config: &Config) -> Result<String, VersionWiseError> {
+    open_path(&config.version_paths[0], &config.version_scheme)
 }
 
-pub fn find_version_in_file() -> Vec<String> {
-    // Search the `pyproject.toml` in the root folder
-    let route = "pyproject.toml";
-
-    // Read the pyproject.toml content
-    let config = match fs::read_to_string(route) {
-        Ok(config) => config,
-        Err(e) => {
-            panic!("Error reading the `pyproject.toml` file: {}", e);
-        }
-    };
+/// Read the version written in a version file
+pub fn open_path(
+    version_path: &VersionPath,
+    scheme: &VersionScheme,
+) -> Result<String, VersionWiseError> {
+    // Read the content as a String
+    let path = &version_path.path;
+    let content = fs::read_to_string(path).map_err(|e| VersionWiseError::io(path, e))?;
+    // Find the version inside of it. It is returned as it's written
+    let range = locate_version(version_path, &content, scheme)?;
+    Ok(content[range].to_string())
+}
 
-    // Parse the content as a TOML file
-    let toml_config: Value = match config.parse() {
-        Ok(toml_config) => toml_config,
-        Err(e) => {
-            panic!("Error getting the file {}: {}", route, e)
-        }
-    };
+/// Compute the new content of every version file, without writing anything.
+/// Returns the path, the current content and the updated content of each file
+/// that would change.
+///
+/// Only the version of each file is replaced, so other occurrences of the same
+/// string (like pinned dependencies) and the formatting are kept.
+pub fn version_path_updates(
+    config: &Config,
+    new_version: &str,
+) -> Result<Vec<(String, String, String)>, VersionWiseError> {
+    // Store the updates here
+    let mut updates = Vec::new();
 
-    // Search the [tool.versionwise] version path
-    let mut version_paths: Vec<String> = Vec::new();
-    if let Some(tool) = toml_config.get("tool") {
-        if let Some(versionwise) = tool.get("versionwise") {
-            if let Some(possible_paths) = versionwise.get("version_path") {
-                if let Some(paths) = possible_paths.as_array() {
-                    for path in paths {
-                        version_paths.push(path.to_string().replace("\"", ""));
-                    }
-                } else {
-                    panic!("The version path doesn't include a path");
-                }
-            } else {
-                panic!("The versionwise utility doesn't include a `version_path` field")
-            }
-        } else {
-            panic!(
-                "The pyproject doesn't have a versionwise as tool. You should have [tool.versionwise]."
-            )
+    for version_path in &config.version_paths {
+        // Read the content as a String
+        let path = version_path.path.clone();
+        let content = fs::read_to_string(&path).map_err(|e| VersionWiseError::io(&path, e))?;
+        // Substitute the old version for the new version
+        let range = locate_version(version_path, &content, &config.version_scheme)?;
+        let mut updated_content = content.clone();
+        updated_content.replace_range(range, new_version);
+        // Only keep the files that change
+        if updated_content != content {
+            updates.push((path, content, updated_content));
         }
-    } else {
-        panic!("The pyproject doesn't have tools associated. Please add the `versionwise` tool as [tool.versionwise].")
     }
-    if version_paths.is_empty() {
-        panic!("Couldn't find any version paths in the configuration.")
-    }
-    // Return the version paths
-    version_paths
+    Ok(updates)
 }
 
-pub fn open_path(path: String) -> String {
-    // Open the file
-    let file = match fs::File::open(path.clone()) {
-        Ok(file) => file,
-        Err(e) => {
-            panic!("Error opening file {}: {}.", path, e);
-        }
-    };
-    // Create the buffer to read the file
-    let reader = BufReader::new(file);
-    // Iterate over the lines in the file to get the version
-    for line in reader.lines() {
-        if let Ok(line) = line {
-            // Verify if the line has the pattern
-            if line.contains("version =") || line.contains("__version__ =") {
-                // Initialize the process extraction
-                let pattern = r#""(\d+\.\d+\.\d+)""#;
-                // Compilar el patrón de expresión regular
-                let re = Regex::new(pattern).unwrap();
-                if let Some(captures) = re.captures(&line) {
-                    if let Some(version) = captures.get(1) {
-                        return version.as_str().to_string();
-                    }
-                } else {
-                    panic!(
-                        "In the line \"{}\" it cannot be found a version number.",
-                        line
-                    );
-                }
-            }
-        } else {
-            panic!("Error reading the file {}.", path);
-        }
+/// Read the version written in every version file, as pairs of path and version
+pub fn read_versions(config: &Config) -> Result<Vec<(String, String)>, VersionWiseError> {
+    config
+        .version_paths
+        .iter()
+        .map(|version_path| {
+            let version = open_path(version_path, &config.version_scheme)?;
+            Ok((version_path.path.clone(), version))
+        })
+        .collect()
+}
+
+/// Whether all the versions are the same, once normalized by the version scheme
+pub fn versions_match(
+    versions: &[(String, String)],
+    scheme: &VersionScheme,
+) -> Result<bool, VersionWiseError> {
+    let mut normalized = Vec::new();
+    for (_, version) in versions {
+        normalized.push(scheme.normalize(version)?);
     }
-    // If it reaches here, then it couldn't find the `version`
-    panic!("Couldn't find the version in the path {}. Try with the following version names: [\"version\", \"__version__\"]", path);
+    Ok(normalized.windows(2).all(|pair| pair[0] == pair[1]))
 }
 
-fn update_version_path(new_version: &str) {
-    // Find all version paths
-    let version_paths = find_version_in_file();
-    // Get the current version
-    let current_version = find_version();
+/// Fail if the version files don't have the same version
+pub fn check_versions_consistency(config: &Config) -> Result<(), VersionWiseError> {
+    let versions = read_versions(config)?;
+    if versions_match(&versions, &config.version_scheme)? {
+        return Ok(());
+    }
+    let found: Vec<String> = versions
+        .iter()
+        .map(|(path, version)| format!("`{}` in {}", version, path))
+        .collect();
+    Err(VersionWiseError::Version(format!(
+        "The version files are not consistent: {}. Run `versionwise doctor --fix` to align them.",
+        found.join(", ")
+    )))
+}
 
+/// Write the new version in every version file
+pub fn update_version_path(config: &Config, new_version: &str) -> Result<(), VersionWiseError> {
     // Update each file
-    for version_path in version_paths {
-        // Open the file
-        let mut file = match fs::File::open(&version_path) {
-            Ok(file) => file,
-            Err(e) => {
-                panic!("Error opening file {}: {}.", version_path, e);
-            }
-        };
-        // Read the content as a String
-        let mut content = String::new();
-        if let Err(e) = file.read_to_string(&mut content) {
-            panic!("Error reading file {}: {}.", version_path, e);
-        }
-        // Substitute the old version for the new version
-        let updated_content = content.replace(&current_version, new_version);
-        // Reopen the file but this time as writing mode
-        file = match fs::File::create(&version_path) {
-            Ok(file) => file,
-            Err(e) => {
-                panic!("Error creating file {}: {}.", version_path, e);
-            }
-        };
-        // Write the new file
-        if let Err(e) = file.write_all(updated_content.as_bytes()) {
-            panic!("Error writing to file {}: {}.", version_path, e);
-        }
+    for (version_path, _, updated_content) in version_path_updates(config, new_version)? {
+        fs::write(&version_path, updated_content)
+            .map_err(|e| VersionWiseError::io(&version_path, e))?;
     }
+    Ok(())
 }
 
-/// Find the largest version in a list of changesets
-pub fn find_largest_version(changesets: &[Changeset]) -> Option<String> {
-    changesets
-        .iter()
-        .filter_map(|c| parse_version(&c.version)) // Parse the versions
-        .max() // Obtain the largest version
-        .map(|(major, minor, patch)| format!("{}.{}.{}", major, minor, patch)) // Convert it back to String
+/// Calculate the next version from the current one, in the version file, and
+/// the highest change type of the changesets. The `version` stored in each
+/// changeset is only informational, since it can be stale after other releases.
+/// Returns `None` if there are no changesets.
+pub fn calculate_release_version(
+    config: &Config,
+    changesets: &[Changeset],
+) -> Result<Option<String>, VersionWiseError> {
+    let scheme = &config.version_scheme;
+    let Some(change) = highest_change_type(changesets)? else {
+        return Ok(None);
+    };
+    let current_version = find_version(config)?;
+    let version = scheme.bump(&current_version, change)?;
+    // The release should always go forward from the version in the file
+    if !scheme.compare(&version, &current_version)?.is_gt() {
+        return Err(VersionWiseError::Version(format!(
+            "The release version `{}` is not newer than the current version `{}`.",
+            version, current_version
+        )));
+    }
+    Ok(Some(version))
 }
 
-/// Parse a version "MAJOR.MINOR.PATCH" into a tuple (u32, u32, u32)
-fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
-    let parts: Vec<u32> = version
-        .split('.') // Divide into parts
-        .filter_map(|p| p.parse().ok()) // Convert to u32
-        .collect();
+/// Release that `bump` would produce with the current changesets
+pub struct NextRelease {
+    /// Version to release
+    pub version: String,
+    /// Changesets to include in the CHANGELOG entry
+    pub changesets: Vec<Changeset>,
+    /// Pre-release state to store after the release. It is `None` for the
+    /// regular releases, where the changesets are deleted
+    pub pre_state: Option<PreState>,
+    /// Whether the version changes. It doesn't when every changeset has the
+    /// `none` change type, and then `version` is the current version
+    pub bumps_version: bool,
+}
+
+/// Whether every changeset has the `none` change type
+fn only_no_bump<'a>(changesets: impl IntoIterator<Item = &'a Changeset>) -> bool {
+    changesets
+        .into_iter()
+        .all(|changeset| changeset.is_no_bump())
+}
 
-    if parts.len() == 3 {
-        Some((parts[0], parts[1], parts[2]))
-    } else {
-        Some((0, 0, 0))
+/// Calculate the next release from the changesets and the pre-release state.
+/// Returns `None` if there's nothing to release.
+pub fn next_release(
+    config: &Config,
+    changesets: Vec<Changeset>,
+) -> Result<Option<NextRelease>, VersionWiseError> {
+    let scheme = &config.version_scheme;
+    match read_pre_state(config)? {
+        // Regular release
+        None if !changesets.is_empty() && only_no_bump(&changesets) => Ok(Some(NextRelease {
+            version: find_version(config)?,
+            changesets,
+            pre_state: None,
+            bumps_version: false,
+        })),
+        None => Ok(
+            calculate_release_version(config, &changesets)?.map(|version| NextRelease {
+                version,
+                changesets,
+                pre_state: None,
+                bumps_version: true,
+            }),
+        ),
+        // Final release of the pre-release cycle. It collapses every changeset,
+        // including the ones already released in a pre-release
+        Some(state) if state.mode == PreMode::Exit => {
+            if changesets.is_empty() {
+                return Ok(None);
+            }
+            let bumps_version = !only_no_bump(&changesets);
+            let version = match bumps_version {
+                true => state.release_version(&changesets, scheme)?,
+                false => find_version(config)?,
+            };
+            Ok(Some(NextRelease {
+                version,
+                changesets,
+                pre_state: None,
+                bumps_version,
+            }))
+        }
+        // New pre-release, only with the changesets that were not released yet
+        Some(mut state) => {
+            let bumps_version = !only_no_bump(
+                changesets
+                    .iter()
+                    .filter(|c| !state.consumed.contains(&c.name)),
+            );
+            let version = match bumps_version {
+                true => state.pre_release_version(&changesets, scheme)?,
+                false => find_version(config)?,
+            };
+            let pending: Vec<Changeset> = changesets
+                .into_iter()
+                .filter(|c| !state.consumed.contains(&c.name))
+                .collect();
+            if pending.is_empty() {
+                return Ok(None);
+            }
+            state
+                .consumed
+                .extend(pending.iter().map(|c| c.name.clone()));
+            // Only a new pre-release takes the next number
+            if bumps_version {
+                state.counter += 1;
+            }
+            Ok(Some(NextRelease {
+                version,
+                changesets: pending,
+                pre_state: Some(state),
+                bumps_version,
+            }))
+        }
     }
 }
@@ -0,0 +1,394 @@
+/// ================================ ///
+///       UTILITIES :: PEP 440       ///
+/// ================================ ///
+/// Python version model (https://peps.python.org/pep-0440/).
+/// Parses any spelling that PEP 440 accepts (`v1.2.0-RC.2`, `1.2.0.post1`,
+/// `1!2.0.dev3`), and formats it in its normalized form.
+use regex::Regex;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+// Local imports
+use crate::utilities::version_operations::ChangeType;
+use crate::utilities::VersionWiseError;
+
+/// Pattern of the PEP 440 appendix, accepting every valid spelling of a version
+const VERSION_PATTERN: &str = r"(?ix)^\s*
+    v?
+    (?:(?P<epoch>[0-9]+)!)?
+    (?P<release>[0-9]+(?:\.[0-9]+)*)
+    (?:
+        [-_\.]?
+        (?P<pre_l>alpha|a|beta|b|preview|pre|rc|c)
+        [-_\.]?
+        (?P<pre_n>[0-9]+)?
+    )?
+    (?:
+        (?:-(?P<post_n1>[0-9]+))
+        |
+        (?:
+            [-_\.]?
+            (?P<post_l>post|rev|r)
+            [-_\.]?
+            (?P<post_n2>[0-9]+)?
+        )
+    )?
+    (?:
+        [-_\.]?
+        (?P<dev_l>dev)
+        [-_\.]?
+        (?P<dev_n>[0-9]+)?
+    )?
+    (?:\+(?P<local>[a-z0-9]+(?:[-_\.][a-z0-9]+)*))?
+\s*$";
+
+fn version_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(VERSION_PATTERN).unwrap())
+}
+
+/// Kind of pre-release. They're ordered by their precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PreKind {
+    Alpha,
+    Beta,
+    ReleaseCandidate,
+}
+
+impl PreKind {
+    /// Get the kind from any of its PEP 440 spellings
+    pub fn from_label(label: &str) -> Option<PreKind> {
+        match label.to_lowercase().as_str() {
+            "a" | "alpha" => Some(PreKind::Alpha),
+            "b" | "beta" => Some(PreKind::Beta),
+            "rc" | "c" | "pre" | "preview" => Some(PreKind::ReleaseCandidate),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PreKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreKind::Alpha => write!(f, "a"),
+            PreKind::Beta => write!(f, "b"),
+            PreKind::ReleaseCandidate => write!(f, "rc"),
+        }
+    }
+}
+
+/// A segment of the local version label (`+ubuntu.1`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocalSegment {
+    Number(u64),
+    Text(String),
+}
+
+impl Ord for LocalSegment {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (LocalSegment::Number(a), LocalSegment::Number(b)) => a.cmp(b),
+            (LocalSegment::Text(a), LocalSegment::Text(b)) => a.cmp(b),
+            // Numeric segments always have higher precedence
+            (LocalSegment::Number(_), LocalSegment::Text(_)) => Ordering::Greater,
+            (LocalSegment::Text(_), LocalSegment::Number(_)) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for LocalSegment {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for LocalSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LocalSegment::Number(number) => write!(f, "{}", number),
+            LocalSegment::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+/// A PEP 440 version.
+///
+/// Equality and ordering follow the PEP 440 rules, so `1.0` and `1.0.0`
+/// are the same version.
+#[derive(Debug, Clone)]
+pub struct Pep440Version {
+    pub epoch: u64,
+    pub release: Vec<u64>,
+    pub pre: Option<(PreKind, u64)>,
+    pub post: Option<u64>,
+    pub dev: Option<u64>,
+    pub local: Vec<LocalSegment>,
+}
+
+impl Pep440Version {
+    /// Create a final release from its release segment
+    pub fn new(release: Vec<u64>) -> Pep440Version {
+        Pep440Version {
+            epoch: 0,
+            release,
+            pre: None,
+            post: None,
+            dev: None,
+            local: Vec::new(),
+        }
+    }
+
+    /// Whether this is a pre-release or a development release
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.is_some() || self.dev.is_some()
+    }
+
+    /// Get a release number, where the missing ones are zero
+    fn part(&self, index: usize) -> u64 {
+        self.release.get(index).copied().unwrap_or(0)
+    }
+
+    /// Calculate the next final release for the given change type, over
+    /// the `MAJOR.MINOR.MICRO` release segment. The epoch is kept.
+    ///
+    /// Like with SemVer, a pre-release or development release is released as
+    /// its own version when the change fits in it, so `1.0.0rc1` with a MAJOR
+    /// change gives `1.0.0`.
+    pub fn bump(&self, change: ChangeType) -> Pep440Version {
+        let (major, minor, micro) = (self.part(0), self.part(1), self.part(2));
+        let pre = self.is_prerelease();
+        let release = match change {
+            ChangeType::Major if pre && minor == 0 && micro == 0 => vec![major, 0, 0],
+            ChangeType::Major => vec![major + 1, 0, 0],
+            ChangeType::Minor if pre && micro == 0 => vec![major, minor, 0],
+            ChangeType::Minor => vec![major, minor + 1, 0],
+            ChangeType::Patch if pre => vec![major, minor, micro],
+            ChangeType::Patch => vec![major, minor, micro + 1],
+            ChangeType::None => return self.clone(),
+        };
+        Pep440Version {
+            epoch: self.epoch,
+            ..Pep440Version::new(release)
+        }
+    }
+
+    /// The release segment without its trailing zeros, used to compare versions
+    fn trimmed_release(&self) -> &[u64] {
+        let end = self
+            .release
+            .iter()
+            .rposition(|number| *number != 0)
+            .map_or(0, |index| index + 1);
+        &self.release[..end]
+    }
+
+    /// Position of the pre-release part in the ordering
+    fn pre_key(&self) -> PreKey {
+        match self.pre {
+            Some((kind, number)) => PreKey::Pre(kind, number),
+            None if self.post.is_none() && self.dev.is_some() => PreKey::DevOnly,
+            None => PreKey::Final,
+        }
+    }
+}
+
+/// Position of the pre-release part of a version in the ordering. A development
+/// release of a final version (`1.0.dev1`) sorts before its pre-releases, and
+/// the final version after them.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum PreKey {
+    DevOnly,
+    Pre(PreKind, u64),
+    Final,
+}
+
+fn invalid_version(version: &str) -> VersionWiseError {
+    VersionWiseError::Version(format!(
+        "`{}` is not a valid PEP 440 version. Expected something like `1.2.0`, `1.2.0rc1`, `1.2.0.post1` or `1.2.0.dev3`",
+        version
+    ))
+}
+
+impl FromStr for Pep440Version {
+    type Err = VersionWiseError;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let captures = version_regex()
+            .captures(version)
+            .ok_or_else(|| invalid_version(version))?;
+        // All the numbers have been validated by the pattern, but they can overflow
+        let number = |name: &str| -> Result<Option<u64>, VersionWiseError> {
+            captures
+                .name(name)
+                .map(|n| n.as_str().parse().map_err(|_| invalid_version(version)))
+                .transpose()
+        };
+        let mut parsed = Pep440Version::new(
+            captures["release"]
+                .split('.')
+                .map(|n| n.parse().map_err(|_| invalid_version(version)))
+                .collect::<Result<Vec<u64>, VersionWiseError>>()?,
+        );
+        parsed.epoch = number("epoch")?.unwrap_or(0);
+        // The implicit numbers of the pre, post and dev releases are zero
+        if let Some(label) = captures.name("pre_l") {
+            let kind =
+                PreKind::from_label(label.as_str()).ok_or_else(|| invalid_version(version))?;
+            parsed.pre = Some((kind, number("pre_n")?.unwrap_or(0)));
+        }
+        if captures.name("post_n1").is_some() {
+            parsed.post = number("post_n1")?;
+        } else if captures.name("post_l").is_some() {
+            parsed.post = Some(number("post_n2")?.unwrap_or(0));
+        }
+        if captures.name("dev_l").is_some() {
+            parsed.dev = Some(number("dev_n")?.unwrap_or(0));
+        }
+        // The local segments are normalized to lowercase and split by any separator
+        if let Some(local) = captures.name("local") {
+            parsed.local = local
+                .as_str()
+                .to_lowercase()
+                .split(['-', '_', '.'])
+                .map(|segment| match segment.parse() {
+                    Ok(number) => LocalSegment::Number(number),
+                    Err(_) => LocalSegment::Text(segment.to_string()),
+                })
+                .collect();
+        }
+        Ok(parsed)
+    }
+}
+
+impl fmt::Display for Pep440Version {
+    /// Write the normalized form of the version
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.epoch != 0 {
+            write!(f, "{}!", self.epoch)?;
+        }
+        let release: Vec<String> = self.release.iter().map(|n| n.to_string()).collect();
+        write!(f, "{}", release.join("."))?;
+        if let Some((kind, number)) = self.pre {
+            write!(f, "{}{}", kind, number)?;
+        }
+        if let Some(post) = self.post {
+            write!(f, ".post{}", post)?;
+        }
+        if let Some(dev) = self.dev {
+            write!(f, ".dev{}", dev)?;
+        }
+        if !self.local.is_empty() {
+            let local: Vec<String> = self.local.iter().map(|s| s.to_string()).collect();
+            write!(f, "+{}", local.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for Pep440Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.epoch
+            .cmp(&other.epoch)
+            .then_with(|| self.trimmed_release().cmp(other.trimmed_release()))
+            .then_with(|| self.pre_key().cmp(&other.pre_key()))
+            // A post release sorts after the version without it
+            .then_with(|| self.post.cmp(&other.post))
+            // A development release sorts before the version without it
+            .then_with(|| other.dev.is_some().cmp(&self.dev.is_some()))
+            .then_with(|| self.dev.cmp(&other.dev))
+            // A local version sorts after the version without it
+            .then_with(|| self.local.cmp(&other.local))
+    }
+}
+
+impl PartialOrd for Pep440Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Pep440Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pep440Version {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(text: &str) -> Pep440Version {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn normalizes_every_spelling() {
+        let spellings = [
+            ("v1.2.0-RC.2", "1.2.0rc2"),
+            ("1.2.0alpha1", "1.2.0a1"),
+            ("1.2.0-beta", "1.2.0b0"),
+            ("1.2.0c1", "1.2.0rc1"),
+            ("1.2.0-1", "1.2.0.post1"),
+            ("1.2.0.rev", "1.2.0.post0"),
+            ("1.2.0_DEV3", "1.2.0.dev3"),
+            ("1!2.0", "1!2.0"),
+            ("1.0+Ubuntu-1_A", "1.0+ubuntu.1.a"),
+        ];
+        for (spelling, normalized) in spellings {
+            assert_eq!(version(spelling).to_string(), normalized, "{}", spelling);
+        }
+        assert!("1.0-foo".parse::<Pep440Version>().is_err());
+    }
+
+    #[test]
+    fn orders_the_releases() {
+        let versions = [
+            "1.0.dev1",
+            "1.0a1.dev1",
+            "1.0a1",
+            "1.0b1",
+            "1.0rc1",
+            "1.0",
+            "1.0+local",
+            "1.0.post1.dev1",
+            "1.0.post1",
+            "1.1",
+            "1!0.1",
+        ];
+        for pair in versions.windows(2) {
+            assert!(
+                version(pair[0]) < version(pair[1]),
+                "{} < {}",
+                pair[0],
+                pair[1]
+            );
+        }
+        // The trailing zeros of the release segment don't count
+        assert_eq!(version("1.0"), version("1.0.0"));
+    }
+
+    #[test]
+    fn bumps_by_change_type() {
+        assert_eq!(version("1.2").bump(ChangeType::Patch).to_string(), "1.2.1");
+        assert_eq!(
+            version("1!1.2.3").bump(ChangeType::Major).to_string(),
+            "1!2.0.0"
+        );
+        assert_eq!(
+            version("1.2.0.post1").bump(ChangeType::Minor).to_string(),
+            "1.3.0"
+        );
+        // A pre-release is released as its own version when the change fits in it
+        assert_eq!(
+            version("1.0.0rc1").bump(ChangeType::Major).to_string(),
+            "1.0.0"
+        );
+        assert_eq!(
+            version("1.0.1.dev3").bump(ChangeType::Minor).to_string(),
+            "1.1.0"
+        );
+    }
+}
@@ -0,0 +1,95 @@
+/// ================================ ///
+///     UTILITIES :: Pre-release     ///
+/// ================================ ///
+/// State of the pre-release mode, persisted in `pre.toml` inside of the changesets folder.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+// Local imports
+use crate::options::Changeset;
+use crate::utilities::version_operations::highest_change_type;
+use crate::utilities::{Config, VersionScheme, VersionWiseError};
+
+/// File, inside of the changesets folder, where the pre-release state is stored
+pub const PRE_STATE_FILE: &str = "pre.toml";
+
+/// Mode of the pre-release state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreMode {
+    /// Every bump produces a new pre-release (`X.Y.Z-<tag>.N`)
+    Pre,
+    /// The next bump produces the final release and leaves the pre-release mode
+    Exit,
+}
+
+/// Pre-release state of the project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreState {
+    pub mode: PreMode,
+    /// Pre-release tag, like `alpha` or `rc`
+    pub tag: String,
+    /// Version of the project when the pre-release mode was entered
+    pub base_version: String,
+    /// Number of the next pre-release
+    pub counter: u64,
+    /// Changesets already released in a pre-release
+    #[serde(default)]
+    pub consumed: Vec<String>,
+}
+
+impl PreState {
+    /// Version of the final release, from the base version and every changeset
+    /// of the pre-release cycle
+    pub fn release_version(
+        &self,
+        changesets: &[Changeset],
+        scheme: &VersionScheme,
+    ) -> Result<String, VersionWiseError> {
+        match highest_change_type(changesets)? {
+            Some(change) => scheme.bump(&self.base_version, change),
+            None => Err(VersionWiseError::Version(
+                "There are no changesets to calculate the new version from.".to_string(),
+            )),
+        }
+    }
+
+    /// Version of the next pre-release (e.g. `1.2.0-rc.0`)
+    pub fn pre_release_version(
+        &self,
+        changesets: &[Changeset],
+        scheme: &VersionScheme,
+    ) -> Result<String, VersionWiseError> {
+        let release = self.release_version(changesets, scheme)?;
+        scheme.pre_release(&release, &self.tag, self.counter)
+    }
+}
+
+fn pre_state_path(config: &Config) -> String {
+    config.changeset_path(PRE_STATE_FILE)
+}
+
+/// Read the pre-release state. Returns `None` if the project is not in pre-release mode
+pub fn read_pre_state(config: &Config) -> Result<Option<PreState>, VersionWiseError> {
+    let path = pre_state_path(config);
+    if !Path::new(&path).is_file() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).map_err(|e| VersionWiseError::io(&path, e))?;
+    toml::from_str(&content)
+        .map(Some)
+        .map_err(|e| VersionWiseError::parse(&path, e))
+}
+
+/// Write the pre-release state
+pub fn write_pre_state(config: &Config, state: &PreState) -> Result<(), VersionWiseError> {
+    let path = pre_state_path(config);
+    let content = toml::to_string(state).map_err(|e| VersionWiseError::parse(&path, e))?;
+    fs::write(&path, content).map_err(|e| VersionWiseError::io(&path, e))
+}
+
+/// Remove the pre-release state, leaving the pre-release mode
+pub fn remove_pre_state(config: &Config) -> Result<(), VersionWiseError> {
+    let path = pre_state_path(config);
+    fs::remove_file(&path).map_err(|e| VersionWiseError::io(&path, e))
+}
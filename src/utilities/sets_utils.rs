@@ -1,69 +1,111 @@
 /// Methods for write, delete and create Changesets
-use std::fs;
-use std::io::Write;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
 // Local imports
 use crate::options::Changeset;
+use crate::utilities::changeset_structures::{ChangeDetails, ChangesetInfo, RawChangeset};
+use crate::utilities::{Config, VersionWiseError};
 
 /// Create the changeset directory on the root project
-pub fn create_changeset_folder() {
-    // Check to see if the folder `.changesets` exists
-    if fs::metadata(".changesets/").is_err() {
+pub fn create_changeset_folder(config: &Config) -> Result<(), VersionWiseError> {
+    // Check to see if the changesets folder exists
+    let folder = &config.changeset_dir;
+    if fs::metadata(folder).is_err() {
         // If it doesn't exist, create it
-        match fs::create_dir(".changesets/") {
-            Ok(_) => {}
-            Err(_) => {
-                panic!("There was an error creating the `.changesets/` directory.")
-            }
-        }
+        fs::create_dir_all(folder).map_err(|e| VersionWiseError::io(folder, e))?;
     }
+    Ok(())
 }
 
 /// Write a changeset file from a Changeset structure
-pub fn write_changeset_file(changeset: &Changeset) {
+pub fn write_changeset_file(
+    config: &Config,
+    changeset: &Changeset,
+) -> Result<(), VersionWiseError> {
     // Write the Changeset file from the object obtained
     // First, obtain the file name
-    let filename = format!(".changesets/{}.toml", &changeset.name);
-    // Then, start generating the message
-    let mut toml_content = String::new();
+    let filename = config.changeset_path(&format!("{}.toml", &changeset.name));
+    // Then, build the same structure that is read back. The serializer
+    // escapes the quotes and backslashes of the message and the modules
+    let raw_changeset = RawChangeset {
+        changeset: ChangesetInfo {
+            // Remove any tab character that might be in the change field
+            change_type: changeset.change.trim().to_string(),
+            tag: changeset.tag.clone(),
+            version: changeset.version.clone(),
+        },
+        changes: ChangeDetails {
+            modules: changeset
+                .modules
+                .split(", ")
+                .filter(|module| !module.is_empty())
+                .map(|module| module.to_string())
+                .collect(),
+            description: changeset.message.clone(),
+        },
+    };
+    let toml_content =
+        toml::to_string(&raw_changeset).map_err(|e| VersionWiseError::parse(&filename, e))?;
 
-    // Write [changeset] section - match the RawChangeset structure
-    toml_content.push_str("[changeset]\n");
-    // Remove any tab character that might be in the change field
-    let clean_change = changeset.change.trim_start().trim_end();
-    toml_content.push_str(&format!("change_type = \"{}\"\n", clean_change));
-    toml_content.push_str(&format!("tag = \"{}\"\n", &changeset.tag));
-    toml_content.push_str(&format!("version = \"{}\"\n", &changeset.version));
-    toml_content.push('\n');
+    // Then, create the file. An existing changeset is never replaced
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&filename)
+        .map_err(|e| match e.kind() {
+            ErrorKind::AlreadyExists => VersionWiseError::Input(format!(
+                "The changeset `{}` already exists. Use another name.",
+                filename
+            )),
+            _ => VersionWiseError::io(&filename, e),
+        })?;
+    file.write_all(toml_content.as_bytes())
+        .map_err(|e| VersionWiseError::io(&filename, e))
+}
 
-    // Write [changes] section - match the RawChangeset structure
-    toml_content.push_str("[changes]\n");
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::get_current_changesets;
 
-    // Check if modules exists and format as array
-    if !changeset.modules.is_empty() {
-        toml_content.push_str(&format!(
-            "modules = [\"{}\"]",
-            &changeset.modules.replace(", ", "\", \"")
-        ));
-    } else {
-        toml_content.push_str("modules = []");
+    fn config(dir: &tempfile::TempDir) -> Config {
+        Config::from_toml(&format!(
+            "version_path = [\"pyproject.toml\"]\nchangeset_dir = \"{}\"",
+            dir.path().display()
+        ))
     }
-    toml_content.push('\n');
 
-    // Add description (matches the RawChangeset structure)
-    toml_content.push_str(&format!("description = \"{}\"\n", &changeset.message));
+    fn changeset(name: &str, message: &str) -> Changeset {
+        Changeset::new(
+            name.to_string(),
+            "PATCH".to_string(),
+            "src/a\"b.rs, C:\\dir".to_string(),
+            "Bug".to_string(),
+            message.to_string(),
+            "1.0.1".to_string(),
+        )
+    }
 
-    // Then, create the file
-    let file: Result<fs::File, std::io::Error> = fs::File::create(filename);
+    #[test]
+    fn quotes_and_backslashes_survive_the_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config(&dir);
+        let message = "Fix \"quoted\" path C:\\dir\nand a 'second' line";
+        write_changeset_file(&config, &changeset("quoted", message)).unwrap();
+        let changesets = get_current_changesets(&config).unwrap();
+        assert_eq!(changesets.len(), 1);
+        assert_eq!(changesets[0].name, "quoted");
+        assert_eq!(changesets[0].message, message);
+        assert_eq!(changesets[0].modules, "src/a\"b.rs, C:\\dir");
+    }
 
-    match file {
-        Ok(mut file) => match file.write_all(toml_content.as_bytes()) {
-            Ok(_) => {}
-            Err(_) => {
-                panic!("There's an error writing the changeset.")
-            }
-        },
-        Err(_) => {
-            println!("There was an error creating a Changeset.");
-        }
+    #[test]
+    fn never_replaces_a_changeset() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config(&dir);
+        write_changeset_file(&config, &changeset("same", "First")).unwrap();
+        let error = write_changeset_file(&config, &changeset("same", "Second")).unwrap_err();
+        assert!(matches!(error, VersionWiseError::Input(_)));
+        assert_eq!(get_current_changesets(&config).unwrap()[0].message, "First");
     }
 }
@@ -1,7 +1,7 @@
 /// ================================ ///
 ///      UTILITIES :: SubCommand     ///
 /// ================================ ///
-use clap::Command;
+use clap::{Arg, ArgAction, Command};
 
 // create a type APP to avoid write it on every command
 type CLIApp = Command;
@@ -10,7 +10,47 @@ fn add_create_subcommand() -> CLIApp {
     // Here, create the subcommand `create`
     let create_subcommand: CLIApp = Command::new("create")
         .about("Create a new Changeset")
-        .long_about("With a bunch of options, create the new changeset for a set of development");
+        .long_about("With a bunch of options, create the new changeset for a set of development.
+
+Every field can be given as a flag. The fields that are missing are asked interactively, unless the standard input is not a terminal (CI, scripts), where the command fails instead.")
+        .arg(
+            Arg::new("type")
+                .long("type")
+                .short('t')
+                .help("Change type of the changeset")
+                .value_parser(["major", "minor", "patch", "none", "internal"])
+                .ignore_case(true),
+        )
+        .arg(
+            Arg::new("tag")
+                .long("tag")
+                .help("Tag of the change (e.g. `Feature`, `Bug`)"),
+        )
+        .arg(
+            Arg::new("module")
+                .long("module")
+                .help("Module or file affected by the change. Can be repeated")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("message")
+                .long("message")
+                .short('m')
+                .help("Message that describes the change"),
+        )
+        .arg(
+            Arg::new("name")
+                .long("name")
+                .short('n')
+                .help("Name of the changeset file, without `/`, `\\` or `..`. A random one is used if missing"),
+        )
+        .arg(
+            Arg::new("yes")
+                .long("yes")
+                .short('y')
+                .help("Save the changeset without asking for confirmation")
+                .action(ArgAction::SetTrue),
+        );
     // Return the subcommand
     create_subcommand
 }
@@ -19,7 +59,17 @@ fn add_list_subcommand() -> CLIApp {
     // Here, create the subcommand `list`
     let list_subcommand: CLIApp = Command::new("list")
         .about("List all the current Changesets")
-        .long_about("List the current changesets and a short description from them. Doesn't require arguments.");
+        .long_about("List the current changesets and a short description from them.
+
+Use `--format` to get a machine-readable output (JSON or TOML) with the current version, the next version and every pending changeset, or the Markdown entry that `bump` would write in the CHANGELOG.")
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .short('f')
+                .help("Output format of the list")
+                .value_parser(["text", "json", "toml", "markdown"])
+                .default_value("text"),
+        );
     // Return the subcommand
     list_subcommand
 }
@@ -28,22 +78,155 @@ fn add_bump_subcommand() -> CLIApp {
     // Here, create the subcommand `bump`
     let bump_subcommand: CLIApp = Command::new("bump")
         .about("Bump the new version using the pending changesets")
-        .long_about("Using all the current changesets that we have locally, and delete them after update the changelog and the version of the package.
-        
-It is also going to delete all the current files in the `.changesets` folder (to restart the process)");
+        .long_about("Using all the current changesets that we have locally, update the changelog and the version of the package.
+
+After the release, the changesets are deleted, or moved to `.changesets/archive/<version>/` with `archive_changesets = true`. In the pre-release mode they are kept for the final release, and only recorded as consumed in `.changesets/pre.toml`.")
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("Preview the new version, the CHANGELOG entry and the diff of the version files without writing anything")
+                .action(ArgAction::SetTrue),
+        );
     // Return the subcommand
     bump_subcommand
 }
 
+fn add_check_subcommand() -> CLIApp {
+    // Here, create the subcommand `check`
+    let check_subcommand: CLIApp = Command::new("check")
+        .visible_alias("status")
+        .about("Fail if the current branch changes source files without adding a changeset")
+        .long_about("Compare the current branch against a base reference using git. If it changes source files but doesn't add a new file under `.changesets/`, it fails with exit code 1.
+
+It also prints the changed files that are not covered by any changeset module. The files that match the `ignore` glob patterns of `[tool.versionwise]` are not taken into account.")
+        .arg(
+            Arg::new("base")
+                .long("base")
+                .help("Git reference to compare against. Defaults to the `base_ref` of the configuration, or `origin/main`"),
+        )
+        .arg(
+            Arg::new("strict")
+                .long("strict")
+                .help("Also fail if a changed file is not covered by any changeset module")
+                .action(ArgAction::SetTrue),
+        );
+    // Return the subcommand
+    check_subcommand
+}
+
+fn add_pre_subcommand() -> CLIApp {
+    // Here, create the subcommand `pre`
+    let pre_subcommand: CLIApp = Command::new("pre")
+        .about("Enter or exit the pre-release mode")
+        .long_about("While the pre-release mode is active, `bump` releases `X.Y.Z-<tag>.N` versions, incrementing N on every bump. The changesets are kept, so once you exit the mode, the final release collapses all of them into one CHANGELOG section.
+
+The state is stored in `.changesets/pre.toml`.")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("enter")
+                .about("Enter the pre-release mode with the given tag")
+                .arg(
+                    Arg::new("tag")
+                        .help("Pre-release tag, like `alpha`, `beta` or `rc`")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("exit")
+                .about("Exit the pre-release mode. The next `bump` releases the final version"),
+        );
+    // Return the subcommand
+    pre_subcommand
+}
+
+fn add_doctor_subcommand() -> CLIApp {
+    // Here, create the subcommand `doctor`
+    let doctor_subcommand: CLIApp = Command::new("doctor")
+        .about("Check that every version file has the same version")
+        .long_about("Read the version of every file in `version_path` and report the ones that don't match. It fails if they're not consistent, which is also checked before every `bump`.
+
+With `--fix`, every file is aligned to the given version. Without a value, the version is chosen interactively, or taken from the first `version_path` when the standard input is not a terminal.")
+        .arg(
+            Arg::new("fix")
+                .long("fix")
+                .value_name("VERSION")
+                .help("Write the same version in every version file")
+                .num_args(0..=1),
+        );
+    // Return the subcommand
+    doctor_subcommand
+}
+
+fn add_notes_subcommand() -> CLIApp {
+    // Here, create the subcommand `notes`
+    let notes_subcommand: CLIApp = Command::new("notes")
+        .about("Print the release notes of a version from the CHANGELOG")
+        .long_about("Extract the section of a version from the CHANGELOG, to publish it as the release notes.
+
+The version can be `latest` (the default), `unreleased`, a version like `0.4.0`, or a range `0.2.0..0.4.0` with the releases after 0.2.0 up to 0.4.0. A single release is printed without its heading, unless `--heading` is given.")
+        .arg(
+            Arg::new("version")
+                .help("Version, `latest`, `unreleased` or a range like `0.2.0..0.4.0`")
+                .default_value("latest"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .short('f')
+                .help("Output format of the notes")
+                .value_parser(["markdown", "text", "json"])
+                .default_value("markdown"),
+        )
+        .arg(
+            Arg::new("heading")
+                .long("heading")
+                .help("Include the heading of the release")
+                .action(ArgAction::SetTrue),
+        );
+    // Return the subcommand
+    notes_subcommand
+}
+
+fn add_changelog_subcommand() -> CLIApp {
+    // Here, create the subcommand `changelog`
+    let changelog_subcommand: CLIApp = Command::new("changelog")
+        .about("Maintain the CHANGELOG as a whole")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("rebuild")
+                .about("Write the CHANGELOG again from the archived changesets")
+                .long_about("Render every release of the CHANGELOG again from the changesets archived in `.changesets/archive/<version>/`, with the current format, template and tags. Use it to change the style of the CHANGELOG, or to fix a typo of an old entry in its changeset.
+
+The text before the releases, the dates of the releases and the releases without archived changesets are kept. The changesets are only archived with `archive_changesets = true`.")
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Print the rebuilt CHANGELOG without writing it")
+                        .action(ArgAction::SetTrue),
+                ),
+        );
+    // Return the subcommand
+    changelog_subcommand
+}
+
 /// Create and append the subcommands  for the CLI application
-pub fn create_subcommands() -> (CLIApp, CLIApp, CLIApp) {
-    // Create the `create`` subcommand
-    let create = add_create_subcommand();
-    // Create the `list` subcommand
-    let list = add_list_subcommand();
-    // Create the `bump` subcommand
-    let bump = add_bump_subcommand();
-    // Create the extra commands
-    // Return the commands
-    (create, list, bump)
+pub fn create_subcommands() -> Vec<CLIApp> {
+    vec![
+        // Create the `create` subcommand
+        add_create_subcommand(),
+        // Create the `list` subcommand
+        add_list_subcommand(),
+        // Create the `bump` subcommand
+        add_bump_subcommand(),
+        // Create the `check` subcommand
+        add_check_subcommand(),
+        // Create the `pre` subcommand
+        add_pre_subcommand(),
+        // Create the `doctor` subcommand
+        add_doctor_subcommand(),
+        // Create the `notes` subcommand
+        add_notes_subcommand(),
+        // Create the `changelog` subcommand
+        add_changelog_subcommand(),
+    ]
 }
@@ -0,0 +1,314 @@
+/// ================================ ///
+///       UTILITIES :: Version       ///
+/// ================================ ///
+/// Semantic Versioning 2.0.0 model (https://semver.org).
+/// Parses, orders and formats versions like `1.2.3-rc.1+build.5`.
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+// Local imports
+use crate::utilities::version_operations::ChangeType;
+use crate::utilities::VersionWiseError;
+
+/// A dot-separated identifier of the pre-release part of a version
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Identifier {
+    /// Identifier with only digits, compared numerically
+    Numeric(u64),
+    /// Identifier with letters or hyphens, compared in ASCII order
+    AlphaNumeric(String),
+}
+
+impl Ord for Identifier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Identifier::Numeric(a), Identifier::Numeric(b)) => a.cmp(b),
+            (Identifier::AlphaNumeric(a), Identifier::AlphaNumeric(b)) => a.cmp(b),
+            // Numeric identifiers always have lower precedence
+            (Identifier::Numeric(_), Identifier::AlphaNumeric(_)) => Ordering::Less,
+            (Identifier::AlphaNumeric(_), Identifier::Numeric(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Identifier::Numeric(number) => write!(f, "{}", number),
+            Identifier::AlphaNumeric(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+/// A Semantic Versioning 2.0.0 version.
+///
+/// The ordering follows the SemVer precedence rules. Since the build metadata
+/// doesn't have precedence, it is only used to break ties, so the ordering
+/// stays consistent with the equality.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Vec<Identifier>,
+    pub build: Vec<String>,
+}
+
+impl Version {
+    /// Create a release version, without pre-release or build metadata
+    pub fn new(major: u64, minor: u64, patch: u64) -> Version {
+        Version {
+            major,
+            minor,
+            patch,
+            pre: Vec::new(),
+            build: Vec::new(),
+        }
+    }
+
+    /// Whether this is a pre-release version (e.g. `1.0.0-rc.1`)
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty()
+    }
+
+    /// Compare two versions by their SemVer precedence, ignoring the build metadata
+    pub fn cmp_precedence(&self, other: &Version) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                // A pre-release has lower precedence than the release
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                // Compare them identifier by identifier. If all of them are equal,
+                // the larger set of identifiers has the higher precedence
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+
+    /// Calculate the next release version for the given change type.
+    ///
+    /// A pre-release is released as its own version when the change fits in it,
+    /// so `1.0.0-rc.1` with a MAJOR change gives `1.0.0`, while `1.2.3` gives `2.0.0`.
+    /// The build metadata is always dropped.
+    pub fn bump(&self, change: ChangeType) -> Version {
+        let pre = self.is_prerelease();
+        match change {
+            ChangeType::Major if pre && self.minor == 0 && self.patch == 0 => {
+                Version::new(self.major, 0, 0)
+            }
+            ChangeType::Major => Version::new(self.major + 1, 0, 0),
+            ChangeType::Minor if pre && self.patch == 0 => Version::new(self.major, self.minor, 0),
+            ChangeType::Minor => Version::new(self.major, self.minor + 1, 0),
+            ChangeType::Patch if pre => Version::new(self.major, self.minor, self.patch),
+            ChangeType::Patch => Version::new(self.major, self.minor, self.patch + 1),
+            ChangeType::None => self.clone(),
+        }
+    }
+}
+
+/// Parse a numeric part of the version. Leading zeros are not allowed
+fn parse_number(part: &str, name: &str, version: &str) -> Result<u64, VersionWiseError> {
+    if part.len() > 1 && part.starts_with('0') {
+        return Err(invalid_version(
+            version,
+            &format!("the {} number has leading zeros", name),
+        ));
+    }
+    part.parse()
+        .map_err(|_| invalid_version(version, &format!("the {} should be a number", name)))
+}
+
+/// Check that a dot-separated list of identifiers only uses `[0-9A-Za-z-]`
+fn split_identifiers<'a>(
+    part: &'a str,
+    name: &str,
+    version: &str,
+) -> Result<Vec<&'a str>, VersionWiseError> {
+    let identifiers: Vec<&str> = part.split('.').collect();
+    for identifier in &identifiers {
+        if identifier.is_empty()
+            || !identifier
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            return Err(invalid_version(
+                version,
+                &format!("invalid {} identifier `{}`", name, identifier),
+            ));
+        }
+    }
+    Ok(identifiers)
+}
+
+fn invalid_version(version: &str, reason: &str) -> VersionWiseError {
+    VersionWiseError::Version(format!(
+        "`{}` is not a valid semantic version: {}. Expected MAJOR.MINOR.PATCH[-PRERELEASE][+BUILD]",
+        version, reason
+    ))
+}
+
+impl FromStr for Version {
+    type Err = VersionWiseError;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        // Accept the usual `v` prefix of the git tags
+        let text = version.trim();
+        let text = text.strip_prefix('v').unwrap_or(text);
+        // Split the build metadata and the pre-release
+        let (text, build) = match text.split_once('+') {
+            Some((text, build)) => (text, Some(build)),
+            None => (text, None),
+        };
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (text, None),
+        };
+        // Parse MAJOR.MINOR.PATCH
+        let parts: Vec<&str> = core.split('.').collect();
+        if parts.len() != 3 {
+            return Err(invalid_version(version, "it should have three numbers"));
+        }
+        let mut parsed = Version::new(
+            parse_number(parts[0], "major", version)?,
+            parse_number(parts[1], "minor", version)?,
+            parse_number(parts[2], "patch", version)?,
+        );
+        // Parse the pre-release identifiers
+        if let Some(pre) = pre {
+            for identifier in split_identifiers(pre, "pre-release", version)? {
+                if identifier.chars().all(|c| c.is_ascii_digit()) {
+                    parsed.pre.push(Identifier::Numeric(parse_number(
+                        identifier,
+                        "pre-release",
+                        version,
+                    )?));
+                } else {
+                    parsed
+                        .pre
+                        .push(Identifier::AlphaNumeric(identifier.to_string()));
+                }
+            }
+        }
+        // And the build metadata
+        if let Some(build) = build {
+            parsed.build = split_identifiers(build, "build", version)?
+                .into_iter()
+                .map(|identifier| identifier.to_string())
+                .collect();
+        }
+        Ok(parsed)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if !self.pre.is_empty() {
+            let pre: Vec<String> = self.pre.iter().map(|i| i.to_string()).collect();
+            write!(f, "-{}", pre.join("."))?;
+        }
+        if !self.build.is_empty() {
+            write!(f, "+{}", self.build.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_precedence(other)
+            .then_with(|| self.build.cmp(&other.build))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(text: &str) -> Version {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn precedence_follows_the_spec_example() {
+        // https://semver.org/#spec-item-11
+        let versions = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+        ];
+        for pair in versions.windows(2) {
+            assert!(
+                version(pair[0]) < version(pair[1]),
+                "{} < {}",
+                pair[0],
+                pair[1]
+            );
+        }
+        assert!(version("1.9.0") < version("1.10.0"));
+        assert!(version("1.10.0") < version("2.0.0"));
+    }
+
+    #[test]
+    fn precedence_ignores_the_build_metadata() {
+        assert_eq!(
+            version("1.0.0+build.1").cmp_precedence(&version("1.0.0+build.2")),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn parses_and_formats_back() {
+        assert_eq!(
+            version("v1.2.3-rc.1+build.5").to_string(),
+            "1.2.3-rc.1+build.5"
+        );
+        for invalid in [
+            "1.2",
+            "1.02.3",
+            "1.2.3-",
+            "1.2.3-rc..1",
+            "1.2.3-01",
+            "a.b.c",
+        ] {
+            assert!(invalid.parse::<Version>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn bumps_by_change_type() {
+        assert_eq!(version("1.2.3").bump(ChangeType::Major), version("2.0.0"));
+        assert_eq!(version("1.2.3").bump(ChangeType::Minor), version("1.3.0"));
+        assert_eq!(
+            version("1.2.3+build").bump(ChangeType::Patch),
+            version("1.2.4")
+        );
+        // A pre-release is released as its own version when the change fits in it
+        assert_eq!(
+            version("1.1.0-rc.2").bump(ChangeType::Minor),
+            version("1.1.0")
+        );
+        assert_eq!(
+            version("1.1.0-rc.2").bump(ChangeType::Major),
+            version("2.0.0")
+        );
+    }
+}
@@ -0,0 +1,415 @@
+/// ================================ ///
+///    UTILITIES :: Version files    ///
+/// ================================ ///
+/// Find where the version is written in each kind of version file, so it can
+/// be replaced without touching any other occurrence of the same string.
+///
+/// Each entry of `version_path` is a path, where the version is searched
+/// depending on the kind of file, or a table with a custom `pattern` or `key`:
+///
+/// ```toml
+/// version_path = [
+///     "pyproject.toml",
+///     { path = "Chart.yaml", pattern = '(?m)^appVersion: "(?P<version>[^"]+)"' },
+///     { path = "package.json", key = "version" },
+/// ]
+/// ```
+use regex::Regex;
+use serde::Deserialize;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
+// Local imports
+use crate::utilities::{VersionScheme, VersionWiseError};
+
+/// TOML keys that hold the version of the project, in order of preference
+const TOML_VERSION_KEYS: [&str; 4] = [
+    "project.version",
+    "package.version",
+    "tool.poetry.version",
+    "workspace.package.version",
+];
+
+/// Kind of version file, detected by its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VersionFileKind {
+    /// `pyproject.toml`, `Cargo.toml`, ... with the version in a table key
+    Toml,
+    /// `package.json`, with the version in the top-level `"version"` key
+    Json,
+    /// Python modules, with a `__version__ = "..."` assignment
+    Python,
+    /// Any other file, with the first `version = "..."` assignment
+    Plain,
+}
+
+impl VersionFileKind {
+    fn from_path(path: &str) -> VersionFileKind {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("toml") => VersionFileKind::Toml,
+            Some("json") => VersionFileKind::Json,
+            Some("py") => VersionFileKind::Python,
+            _ => VersionFileKind::Plain,
+        }
+    }
+
+    /// Where the version is expected, for the error messages
+    fn expected(&self) -> String {
+        match self {
+            VersionFileKind::Toml => format!("one of the keys {}", TOML_VERSION_KEYS.join(", ")),
+            VersionFileKind::Json => "the top-level \"version\" key".to_string(),
+            VersionFileKind::Python => "a `__version__ = \"...\"` assignment".to_string(),
+            VersionFileKind::Plain => "a `version = \"...\"` assignment".to_string(),
+        }
+    }
+}
+
+/// Regex of a TOML `key = "value"` line. The key can be dotted
+fn toml_key_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r#"^\s*(?P<key>[A-Za-z0-9_\-. ]+?)\s*=\s*["'](?P<value>[^"'\n]*)["']"#).unwrap()
+    })
+}
+
+/// Regex of the Python `__version__` assignment, with an optional type annotation
+fn python_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r#"(?m)^__version__\s*(?::\s*str\s*)?=\s*["'](?P<value>[^"'\n]*)["']"#).unwrap()
+    })
+}
+
+/// Regex of any `version = "..."` assignment
+fn plain_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r#"(?:__version__|\bversion)\s*=\s*["'](?P<value>[^"'\n]*)["']"#).unwrap()
+    })
+}
+
+/// How the version is found inside of a version file
+#[derive(Debug, Clone)]
+enum VersionLocator {
+    /// Depending on the kind of file
+    Auto,
+    /// Regex with a named `version` group
+    Pattern(Regex),
+    /// Dotted key path of a TOML or JSON file (`tool.poetry.version`)
+    Key(String),
+}
+
+/// An entry of the `version_path` configuration
+#[derive(Debug, Clone)]
+pub struct VersionPath {
+    pub path: String,
+    locator: VersionLocator,
+}
+
+/// An entry of `version_path`, as it's written in the configuration
+#[derive(Debug, Deserialize)]
+#[serde(
+    untagged,
+    expecting = "expected a path, or a table with the `path` and either a `pattern` or a `key`"
+)]
+pub enum RawVersionPath {
+    Path(String),
+    Table(RawVersionPathTable),
+}
+
+/// An entry of `version_path` with a custom `pattern` or `key`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawVersionPathTable {
+    path: String,
+    pattern: Option<String>,
+    key: Option<String>,
+}
+
+impl VersionPath {
+    /// Create an entry where the version is searched depending on the kind of file
+    pub fn new(path: &str) -> VersionPath {
+        VersionPath {
+            path: path.to_string(),
+            locator: VersionLocator::Auto,
+        }
+    }
+
+    /// Validate an entry of `version_path`. Returns the reason if it's invalid
+    pub fn from_raw(raw: RawVersionPath) -> Result<VersionPath, String> {
+        let table = match raw {
+            RawVersionPath::Path(path) => return Ok(VersionPath::new(&path)),
+            RawVersionPath::Table(table) => table,
+        };
+        let locator = match (table.pattern, table.key) {
+            (Some(_), Some(_)) => return Err("use either a `pattern` or a `key`".to_string()),
+            (Some(pattern), None) => {
+                let regex = Regex::new(&pattern)
+                    .map_err(|e| format!("the `pattern` is not a valid regex ({})", e))?;
+                if !regex.capture_names().any(|name| name == Some("version")) {
+                    return Err("the `pattern` needs a named `(?P<version>...)` group".to_string());
+                }
+                VersionLocator::Pattern(regex)
+            }
+            (None, Some(key)) => {
+                if !matches!(
+                    VersionFileKind::from_path(&table.path),
+                    VersionFileKind::Toml | VersionFileKind::Json
+                ) {
+                    return Err("a `key` can only be used with TOML and JSON files".to_string());
+                }
+                VersionLocator::Key(key)
+            }
+            (None, None) => VersionLocator::Auto,
+        };
+        Ok(VersionPath {
+            path: table.path,
+            locator,
+        })
+    }
+}
+
+/// Find the version in a TOML file, keeping track of the current table.
+/// Returns the first of the `keys` that is found, in order of preference
+fn locate_toml(content: &str, keys: &[&str]) -> Option<Range<usize>> {
+    let mut table = String::new();
+    let mut offset = 0;
+    // Store the first match of each key, to return the preferred one
+    let mut found: Vec<(usize, Range<usize>)> = Vec::new();
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('[') {
+            // Table header, like `[project]` or `[[bin]]`
+            let header = trimmed.trim_start_matches('[');
+            let header = header.split(']').next().unwrap_or_default();
+            table = header
+                .split('.')
+                .map(|p| p.trim())
+                .collect::<Vec<_>>()
+                .join(".");
+        } else if let Some(captures) = toml_key_regex().captures(line) {
+            let key: Vec<&str> = captures["key"].split('.').map(|p| p.trim()).collect();
+            let key = key.join(".");
+            let full_key = match table.is_empty() {
+                true => key,
+                false => format!("{}.{}", table, key),
+            };
+            if let Some(index) = keys.iter().position(|k| *k == full_key) {
+                let value = captures.name("value").unwrap();
+                found.push((index, offset + value.start()..offset + value.end()));
+            }
+        }
+        offset += line.len();
+    }
+    found
+        .into_iter()
+        .min_by_key(|(index, _)| *index)
+        .map(|(_, range)| range)
+}
+
+/// Find the string value of a key path (like `["version"]`) in a JSON file
+fn locate_json(content: &str, key_path: &[&str]) -> Option<Range<usize>> {
+    let bytes = content.as_bytes();
+    let mut index = 0;
+    // Key of each open object or array. The arrays and the root don't have one
+    let mut containers: Vec<Option<&str>> = Vec::new();
+    // Last string found, that can be a key
+    let mut last_string: Option<Range<usize>> = None;
+    // Key whose value comes next
+    let mut key: Option<&str> = None;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'"' => {
+                // Skip the whole string, taking care of the escaped quotes
+                let start = index + 1;
+                index += 1;
+                while index < bytes.len() && bytes[index] != b'"' {
+                    if bytes[index] == b'\\' {
+                        index += 1;
+                    }
+                    index += 1;
+                }
+                let end = index.min(bytes.len());
+                match key.take() {
+                    // It is a value. Compare its full key path
+                    Some(key) => {
+                        let parents = containers.iter().skip(1).copied();
+                        let path: Option<Vec<&str>> = parents.chain([Some(key)]).collect();
+                        if path.is_some_and(|path| path == key_path) {
+                            return Some(start..end);
+                        }
+                    }
+                    None => last_string = Some(start..end),
+                }
+            }
+            b':' => key = last_string.take().map(|range| &content[range]),
+            b',' => {
+                key = None;
+                last_string = None;
+            }
+            b'{' | b'[' => {
+                containers.push(key.take());
+                last_string = None;
+            }
+            b'}' | b']' => {
+                containers.pop();
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    None
+}
+
+/// Find the first match of a regex with a `group` group
+fn locate_regex(regex: &Regex, content: &str, group: &str) -> Option<Range<usize>> {
+    regex
+        .captures(content)
+        .and_then(|captures| captures.name(group))
+        .map(|value| value.range())
+}
+
+/// Find the byte range of the version in the content of a version file.
+/// The version is validated with the version scheme.
+pub fn locate_version(
+    version_path: &VersionPath,
+    content: &str,
+    scheme: &VersionScheme,
+) -> Result<Range<usize>, VersionWiseError> {
+    let path = &version_path.path;
+    let kind = VersionFileKind::from_path(path);
+    let (range, expected) = match &version_path.locator {
+        VersionLocator::Auto => (
+            match kind {
+                VersionFileKind::Toml => locate_toml(content, &TOML_VERSION_KEYS),
+                VersionFileKind::Json => locate_json(content, &["version"]),
+                VersionFileKind::Python => locate_regex(python_regex(), content, "value"),
+                VersionFileKind::Plain => locate_regex(plain_regex(), content, "value"),
+            },
+            kind.expected(),
+        ),
+        VersionLocator::Pattern(regex) => (
+            locate_regex(regex, content, "version"),
+            format!("a match of the pattern `{}`", regex),
+        ),
+        VersionLocator::Key(key) => {
+            let keys: Vec<&str> = key.split('.').collect();
+            let range = match kind {
+                VersionFileKind::Json => locate_json(content, &keys),
+                _ => locate_toml(content, &[key.as_str()]),
+            };
+            (range, format!("the key `{}`", key))
+        }
+    };
+    let Some(range) = range else {
+        return Err(VersionWiseError::Version(format!(
+            "Couldn't find the version in the path {}. It should be in {}.",
+            path, expected
+        )));
+    };
+    // Validate it before returning it
+    scheme.normalize(&content[range.clone()])?;
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Line and text of the version found in the content
+    fn locate(version_path: &VersionPath, content: &str) -> (usize, String) {
+        let range = locate_version(version_path, content, &VersionScheme::SemVer).unwrap();
+        let line = content[..range.start].matches('\n').count() + 1;
+        (line, content[range].to_string())
+    }
+
+    fn custom(table: &str) -> Result<VersionPath, String> {
+        VersionPath::from_raw(RawVersionPath::Table(toml::from_str(table).unwrap()))
+    }
+
+    #[test]
+    fn skips_the_dependencies_pinned_to_the_same_version() {
+        let cargo = "[package]\nname = \"a\"\nversion = \"1.0.0\"\n\n[dependencies]\nb = \"1.0.0\"\nc = { version = \"1.0.0\" }\n\n[dependencies.d]\nversion = \"1.0.0\"\n";
+        assert_eq!(
+            locate(&VersionPath::new("Cargo.toml"), cargo),
+            (3, "1.0.0".into())
+        );
+        let pyproject = "[tool.other]\nversion = \"1.0.0\"\n\n[project]\ndependencies = [\"b==1.0.0\"]\nversion = \"1.0.0\"\n";
+        assert_eq!(
+            locate(&VersionPath::new("pyproject.toml"), pyproject),
+            (6, "1.0.0".into())
+        );
+    }
+
+    #[test]
+    fn prefers_project_over_tool_poetry() {
+        let path = VersionPath::new("pyproject.toml");
+        let both = "[tool.poetry]\nversion = \"0.9.0\"\n\n[project]\nversion = \"1.0.0\"\n";
+        assert_eq!(locate(&path, both), (5, "1.0.0".into()));
+        let poetry = "[tool.poetry]\nname = \"a\"\nversion = \"0.9.0\"\n";
+        assert_eq!(locate(&path, poetry), (3, "0.9.0".into()));
+        let workspace = "[workspace.package]\nversion = \"2.0.0\"\n";
+        assert_eq!(locate(&path, workspace), (2, "2.0.0".into()));
+    }
+
+    #[test]
+    fn finds_the_top_level_json_key() {
+        let package = r#"{
+  "name": "a \"version\": \"9.9.9\"",
+  "dependencies": { "version": "1.0.0" },
+  "files": ["version", "1.0.0"],
+  "workspaces": [{ "version": "1.0.0" }],
+  "version": "1.2.3"
+}
+"#;
+        assert_eq!(
+            locate(&VersionPath::new("package.json"), package),
+            (6, "1.2.3".into())
+        );
+        // Without the key, the nested ones are not used
+        let nested = r#"{ "dependencies": { "version": "1.0.0" } }"#;
+        let error = locate_version(
+            &VersionPath::new("package.json"),
+            nested,
+            &VersionScheme::SemVer,
+        );
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn finds_the_python_version() {
+        let module =
+            "other__version__ = \"0.0.1\"\nversion = \"0.0.2\"\n__version__: str = \"1.2.3\"\n";
+        assert_eq!(
+            locate(&VersionPath::new("src/a/__init__.py"), module),
+            (3, "1.2.3".into())
+        );
+    }
+
+    #[test]
+    fn uses_a_custom_pattern_or_key() {
+        let chart = custom(
+            r#"path = "Chart.yaml"
+pattern = '(?m)^appVersion: "(?P<version>[^"]+)"'"#,
+        )
+        .unwrap();
+        let content = "version: 0.1.0\nappVersion: \"1.2.3\"\n";
+        assert_eq!(locate(&chart, content), (2, "1.2.3".into()));
+
+        let toml_key = custom("path = \"pyproject.toml\"\nkey = \"tool.custom.version\"").unwrap();
+        let content = "[project]\nversion = \"0.1.0\"\n\n[tool.custom]\nversion = \"1.2.3\"\n";
+        assert_eq!(locate(&toml_key, content), (5, "1.2.3".into()));
+
+        let json_key = custom("path = \"package.json\"\nkey = \"tool.version\"").unwrap();
+        let content = "{\"version\": \"0.1.0\", \"tool\": {\"version\": \"1.2.3\"}}";
+        assert_eq!(locate(&json_key, content), (1, "1.2.3".into()));
+    }
+
+    #[test]
+    fn validates_the_custom_entries() {
+        assert!(custom("path = \"a.txt\"\npattern = 'v(\\d+)'").is_err());
+        assert!(custom("path = \"a.txt\"\npattern = '('").is_err());
+        assert!(custom("path = \"a.py\"\nkey = \"version\"").is_err());
+        assert!(custom("path = \"a.toml\"\nkey = \"v\"\npattern = '(?P<version>.+)'").is_err());
+    }
+}
@@ -1,38 +1,91 @@
-use regex::Regex;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+// Local imports
+use crate::options::Changeset;
+use crate::utilities::{VersionScheme, VersionWiseError};
+
+/// Type of change of a changeset. They're ordered by their impact on the
+/// version, so the largest change type of a set of changesets is the one to bump.
+/// A `None` change (also written `internal`) is documented in the CHANGELOG,
+/// but it doesn't bump the version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeType {
+    #[serde(alias = "internal")]
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl FromStr for ChangeType {
+    type Err = VersionWiseError;
+
+    fn from_str(change_type: &str) -> Result<Self, Self::Err> {
+        match change_type.trim().to_uppercase().as_str() {
+            "MAJOR" => Ok(ChangeType::Major),
+            "MINOR" => Ok(ChangeType::Minor),
+            "PATCH" => Ok(ChangeType::Patch),
+            "NONE" | "INTERNAL" => Ok(ChangeType::None),
+            _ => Err(VersionWiseError::Version(format!(
+                "Invalid change type `{}`. Must be MAJOR, MINOR, PATCH or NONE",
+                change_type
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ChangeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeType::Major => write!(f, "MAJOR"),
+            ChangeType::Minor => write!(f, "MINOR"),
+            ChangeType::Patch => write!(f, "PATCH"),
+            ChangeType::None => write!(f, "NONE"),
+        }
+    }
+}
 
 /// Calculates the next version based on the current version and change type
 ///
 /// # Arguments
 ///
-/// * `current_version` - The current version string (e.g., "0.1.0")
-/// * `change_type` - The type of change ("MAJOR", "MINOR", or "PATCH")
+/// * `current_version` - The current version string (e.g., "0.1.0" or "1.0.0-rc.1")
+/// * `change_type` - The type of change ("MAJOR", "MINOR", "PATCH" or "NONE")
+/// * `scheme` - The version scheme used to parse and bump the version
 ///
 /// # Returns
 ///
-/// The next version string
+/// The next version string, or a `VersionWiseError::Version` if the current
+/// version or the change type are invalid
 ///
 /// # Examples
 ///
 /// ```
-/// let next_version = calculate_next_version("0.1.0", "MINOR");
+/// let next_version = calculate_next_version("0.1.0", "MINOR", &VersionScheme::SemVer)?;
 /// assert_eq!(next_version, "0.2.0");
 /// ```
-pub fn calculate_next_version(current_version: &str, change_type: &str) -> String {
-    // Parse the current version
-    let re = Regex::new(r"(\d+)\.(\d+)\.(\d+)").unwrap();
-    let caps = re
-        .captures(current_version)
-        .expect("Invalid version format. Expected format: MAJOR.MINOR.PATCH");
-
-    let major: u32 = caps[1].parse().unwrap();
-    let minor: u32 = caps[2].parse().unwrap();
-    let patch: u32 = caps[3].parse().unwrap();
-
+pub fn calculate_next_version(
+    current_version: &str,
+    change_type: &str,
+    scheme: &VersionScheme,
+) -> Result<String, VersionWiseError> {
+    // Parse the change type
+    let change: ChangeType = change_type.parse()?;
     // Calculate the next version based on change type
-    match change_type {
-        "MAJOR" => format!("{}.0.0", major + 1),
-        "MINOR" => format!("{}.{}.0", major, minor + 1),
-        "PATCH" => format!("{}.{}.{}", major, minor, patch + 1),
-        _ => panic!("Invalid change type. Must be MAJOR, MINOR, or PATCH"),
+    scheme.bump(current_version, change)
+}
+
+/// Find the highest change type in a list of changesets.
+/// Returns `None` if there are no changesets.
+pub fn highest_change_type(
+    changesets: &[Changeset],
+) -> Result<Option<ChangeType>, VersionWiseError> {
+    let mut highest = None;
+    for changeset in changesets {
+        let change: ChangeType = changeset.change.parse()?;
+        highest = highest.max(Some(change));
     }
+    Ok(highest)
 }
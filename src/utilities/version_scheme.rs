@@ -0,0 +1,111 @@
+/// ================================ ///
+///    UTILITIES :: Version scheme   ///
+/// ================================ ///
+/// The version scheme decides how the versions are parsed, compared and
+/// bumped. It is set with `version_scheme` in the configuration.
+use std::cmp::Ordering;
+// Local imports
+use crate::utilities::calver::{CalVerFormat, DEFAULT_CALVER_FORMAT};
+use crate::utilities::date::Date;
+use crate::utilities::pep440::{Pep440Version, PreKind};
+use crate::utilities::version::Identifier;
+use crate::utilities::version_operations::ChangeType;
+use crate::utilities::{Version, VersionWiseError};
+
+/// Supported version schemes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionScheme {
+    /// Semantic Versioning 2.0.0 (`1.2.3-rc.1+build.5`). The default one
+    SemVer,
+    /// Python versions (`1.2.3rc1`, `1.2.3.post1`, `1!1.2.3.dev1`)
+    Pep440,
+    /// Calendar versions (`2024.5.0`, `24.05.01`), set by `calver_format`
+    CalVer(CalVerFormat),
+}
+
+impl VersionScheme {
+    /// Get the scheme from its configuration name
+    pub fn from_name(name: &str) -> Result<VersionScheme, VersionWiseError> {
+        match name.to_lowercase().as_str() {
+            "semver" => Ok(VersionScheme::SemVer),
+            "pep440" => Ok(VersionScheme::Pep440),
+            "calver" => Ok(VersionScheme::CalVer(DEFAULT_CALVER_FORMAT.parse()?)),
+            _ => Err(VersionWiseError::Config(format!(
+                "Unknown `version_scheme` `{}`. Use `semver`, `pep440` or `calver`.",
+                name
+            ))),
+        }
+    }
+
+    /// Validate a version, returning its normalized form
+    pub fn normalize(&self, version: &str) -> Result<String, VersionWiseError> {
+        match self {
+            VersionScheme::SemVer => Ok(version.parse::<Version>()?.to_string()),
+            VersionScheme::Pep440 => Ok(version.parse::<Pep440Version>()?.to_string()),
+            VersionScheme::CalVer(format) => format.normalize(version),
+        }
+    }
+
+    /// Compare two versions following the rules of the scheme
+    pub fn compare(&self, a: &str, b: &str) -> Result<Ordering, VersionWiseError> {
+        match self {
+            VersionScheme::SemVer => Ok(a.parse::<Version>()?.cmp(&b.parse::<Version>()?)),
+            VersionScheme::Pep440 => Ok(a
+                .parse::<Pep440Version>()?
+                .cmp(&b.parse::<Pep440Version>()?)),
+            VersionScheme::CalVer(format) => format.compare(a, b),
+        }
+    }
+
+    /// Calculate the next release version for the given change type.
+    ///
+    /// CalVer ignores the change type: the version comes from the current date,
+    /// and the MICRO is incremented for every release of the same period.
+    /// A `None` change keeps the version in every scheme.
+    pub fn bump(&self, version: &str, change: ChangeType) -> Result<String, VersionWiseError> {
+        match self {
+            VersionScheme::SemVer => Ok(version.parse::<Version>()?.bump(change).to_string()),
+            VersionScheme::Pep440 => Ok(version.parse::<Pep440Version>()?.bump(change).to_string()),
+            VersionScheme::CalVer(format) if change == ChangeType::None => {
+                format.normalize(version)
+            }
+            VersionScheme::CalVer(format) => format.bump_at(version, &Date::today()?),
+        }
+    }
+
+    /// Build the `number`-th pre-release of a release version with the given tag.
+    /// For SemVer and CalVer it is `1.2.0-rc.0`, and for PEP 440 `1.2.0rc0` (or `1.2.0.dev0`).
+    pub fn pre_release(
+        &self,
+        release: &str,
+        tag: &str,
+        number: u64,
+    ) -> Result<String, VersionWiseError> {
+        match self {
+            VersionScheme::SemVer => {
+                let mut version: Version = release.parse()?;
+                version.pre = vec![
+                    Identifier::AlphaNumeric(tag.to_string()),
+                    Identifier::Numeric(number),
+                ];
+                Ok(version.to_string())
+            }
+            VersionScheme::Pep440 => {
+                let mut version: Pep440Version = release.parse()?;
+                if tag.eq_ignore_ascii_case("dev") {
+                    version.dev = Some(number);
+                } else {
+                    let kind = PreKind::from_label(tag).ok_or_else(|| {
+                        VersionWiseError::Input(format!(
+                            "PEP 440 doesn't support the pre-release tag `{}`. Use `alpha`, `beta`, `rc` or `dev`.",
+                            tag
+                        ))
+                    })?;
+                    version.pre = Some((kind, number));
+                }
+                Ok(version.to_string())
+            }
+            VersionScheme::CalVer(format) => format.pre_release(release, tag, number),
+        }
+    }
+}